@@ -0,0 +1,132 @@
+use std::sync::OnceLock;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use bytes::{BufMut, Bytes, BytesMut};
+use futures::{StreamExt, TryStreamExt};
+use rand::Rng;
+use tokio::sync::watch::channel;
+use tracing::info;
+
+use wlist_native::common::data::files::information::FileInformation;
+use wlist_native::common::data::files::options::Duplicate;
+use wlist_native::common::data::files::FileLocation;
+use wlist_native::core::client::download::{download_confirm, download_finish, download_request, download_stream};
+use wlist_native::core::client::trash::{trash_delete, trash_trash};
+use wlist_native::core::client::upload::{upload_confirm, upload_extra_md5s, upload_finish, upload_request, upload_stream};
+
+use crate::core::{api, InitializeGuard};
+
+const SIZES: &[usize] = &[4 << 10, 1 << 20, 16 << 20, 64 << 20];
+const CONCURRENCY: &[usize] = &[1, 2, 4, 8];
+const PIECE: usize = 64 << 10;
+const OUTPUT: &str = "run/bench";
+
+struct Sample {
+    operation: &'static str,
+    size: usize,
+    concurrency: usize,
+    elapsed: Duration,
+    ttfb: Option<Duration>,
+    chunks: Vec<Duration>,
+}
+
+impl Sample {
+    fn to_json(&self, version: &str) -> String {
+        let ms = |d: &Duration| d.as_secs_f64() * 1000.0;
+        let mut chunks = self.chunks.iter().map(ms).collect::<Vec<_>>();
+        chunks.sort_by(f64::total_cmp);
+        let percentile = |p: usize| chunks.get((chunks.len().saturating_sub(1)) * p / 100).copied().unwrap_or(0.0);
+        format!(
+            r#"{{"version":"{version}","operation":"{}","size":{},"concurrency":{},"chunks":{},"seconds":{:.6},"mb_per_s":{:.3},"ttfb_ms":{},"chunk_ms":{{"min":{:.3},"p50":{:.3},"p90":{:.3},"max":{:.3}}}}}"#,
+            self.operation, self.size, self.concurrency, chunks.len(), self.elapsed.as_secs_f64(),
+            self.size as f64 / (1 << 20) as f64 / self.elapsed.as_secs_f64(),
+            self.ttfb.as_ref().map(|d| format!("{:.3}", ms(d))).unwrap_or_else(|| "null".to_string()),
+            percentile(0), percentile(50), percentile(90), percentile(100),
+        )
+    }
+}
+
+async fn upload(guard: &InitializeGuard, root: FileLocation, name: String, data: &Bytes, concurrency: usize) -> anyhow::Result<(FileInformation, Sample)> {
+    let chunk = api!(upload_extra_md5s(guard, root.storage))?.map(|chunk| chunk.get());
+    let (md5, md5s) = super::upload::hash(data, chunk).await;
+    let start = Instant::now();
+    let confirmation = api!(upload_request(guard, root, name, data.len() as u64, md5, md5s, Duplicate::Error))?;
+    let mut chunks = Vec::new();
+    if !confirmation.done {
+        let information = api!(upload_confirm(guard, confirmation.token.clone()))?;
+        let token = &confirmation.token;
+        chunks = futures::stream::iter(information.chunks.into_iter().zip(0..)).map(|(chunk, id)| async move {
+            let begin = Instant::now();
+            let mut data = data.slice(chunk.start as usize..(chunk.start + chunk.size) as usize);
+            api!(upload_stream(guard, token.clone(), id, &mut data, channel(0).0, channel(true).1))?;
+            Ok::<_, anyhow::Error>(begin.elapsed())
+        }).buffer_unordered(concurrency).try_collect().await?;
+    }
+    let information = api!(upload_finish(guard, confirmation.token))?;
+    let elapsed = start.elapsed();
+    Ok((information, Sample { operation: "upload", size: data.len(), concurrency, elapsed, ttfb: None, chunks }))
+}
+
+async fn download(guard: &InitializeGuard, location: FileLocation, concurrency: usize) -> anyhow::Result<Sample> {
+    let start = Instant::now();
+    let confirmation = api!(download_request(guard, location, 0, u64::MAX))?;
+    let information = api!(download_confirm(guard, confirmation.token.clone()))?;
+    let token = &confirmation.token;
+    let first = OnceLock::new();
+    let first = &first;
+    let chunks = futures::stream::iter(information.chunks.iter().zip(0..)).map(|(chunk, id)| async move {
+        let begin = Instant::now();
+        let mut done = 0;
+        while done < chunk.size {
+            let size = std::cmp::min(PIECE as u64, chunk.size - done) as usize;
+            let mut buffer = BytesMut::new().limit(size);
+            let (tx, mut rx) = channel(0);
+            let offset = if chunk.range { done } else { 0 };
+            tokio::select! {
+                r = async { api!(download_stream(guard, token.clone(), id, offset, &mut buffer, tx, channel(true).1)) } => r?,
+                _ = async {
+                    while rx.changed().await.is_ok() {
+                        if *rx.borrow_and_update() > 0 { first.get_or_init(|| start.elapsed()); break; }
+                    }
+                    std::future::pending::<()>().await
+                } => unreachable!(),
+            }
+            let len = buffer.into_inner().len();
+            if len > 0 { first.get_or_init(|| start.elapsed()); }
+            done += len as u64;
+            if len < size { break; }
+        }
+        Ok::<_, anyhow::Error>(begin.elapsed())
+    }).buffer_unordered(concurrency).try_collect().await?;
+    api!(download_finish(guard, confirmation.token.clone()))?;
+    let elapsed = start.elapsed();
+    Ok(Sample { operation: "download", size: confirmation.size as usize, concurrency, elapsed, ttfb: first.get().copied(), chunks })
+}
+
+pub async fn benchmark(guard: &InitializeGuard, root: FileLocation) -> anyhow::Result<()> {
+    let version = wlist_native::common::versions::get_core_api_version();
+    let mut lines = Vec::new();
+    for &size in SIZES {
+        let mut data = BytesMut::zeroed(size);
+        rand::thread_rng().fill(&mut data[..]);
+        let data = data.freeze();
+        for &concurrency in CONCURRENCY {
+            let (file, sample) = upload(guard, root, format!("Bench-{size}-{concurrency}.bin"), &data, concurrency).await?;
+            lines.push(sample.to_json(&version));
+            let location = file.get_location(root.storage);
+            let sample = download(guard, location, concurrency).await?;
+            lines.push(sample.to_json(&version));
+            let information = api!(trash_trash(guard, location))?;
+            api!(trash_delete(guard, information.get_location(root.storage)))?;
+        }
+    }
+    for line in &lines {
+        info!(%line, "Benchmark.");
+    }
+    let timestamp = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+    std::fs::create_dir_all(OUTPUT)?;
+    let path = format!("{OUTPUT}/{version}-{timestamp}.jsonl");
+    std::fs::write(&path, lines.join("\n") + "\n")?;
+    info!(%path, "Benchmark results written.");
+    Ok(())
+}
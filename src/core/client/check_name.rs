@@ -1,37 +1,46 @@
+use std::collections::BTreeMap;
+
+use bytes::Bytes;
+
+use wlist_native::common::data::files::options::Duplicate;
 use wlist_native::common::data::files::FileLocation;
-use wlist_native::core::client::upload::upload_check_name;
+use wlist_native::common::data::storages::StorageType;
+use wlist_native::core::client::files::files_rename;
+use wlist_native::core::client::storages::storages_get;
+use wlist_native::core::client::trash::{trash_delete, trash_trash};
+use wlist_native::core::client::upload::{upload_check_name, upload_mkdir};
 
-use crate::core::{c, InitializeGuard};
+use crate::core::{api, InitializeGuard};
 
 pub async fn test_none(guard: &InitializeGuard) -> anyhow::Result<()> {
     let root = FileLocation { storage: 0, file_id: 0, is_directory: true, };
 
     // test_incorrect_parent
     let file = FileLocation { storage: 0, file_id: 0, is_directory: false, };
-    let result = upload_check_name(c!(guard), "chunk.txt".to_string(), file, false).await;
+    let result = api!(upload_check_name(guard, "chunk.txt".to_string(), file, false));
     crate::assert_error::<_, wlist_native::common::exceptions::IncorrectArgumentError>(result)?;
-    let result = upload_check_name(c!(guard), "".to_string(), file, false).await;
+    let result = api!(upload_check_name(guard, "".to_string(), file, false));
     crate::assert_error::<_, wlist_native::common::exceptions::IncorrectArgumentError>(result)?;
-    let result = upload_check_name(c!(guard), "a".repeat(32768), file, false).await;
+    let result = api!(upload_check_name(guard, "a".repeat(32768), file, false));
     crate::assert_error::<_, wlist_native::common::exceptions::IncorrectArgumentError>(result)?;
 
     // test_incorrect_name
-    let result = upload_check_name(c!(guard), "".to_string(), root, false).await;
+    let result = api!(upload_check_name(guard, "".to_string(), root, false));
     crate::assert_error::<_, wlist_native::common::exceptions::IncorrectArgumentError>(result)?;
-    let result = upload_check_name(c!(guard), "a".repeat(32768), root, false).await;
+    let result = api!(upload_check_name(guard, "a".repeat(32768), root, false));
     crate::assert_error::<_, wlist_native::common::exceptions::IncorrectArgumentError>(result)?;
 
     // test_incorrect_storage
-    let result = upload_check_name(c!(guard), "chunk.txt".to_string(), root, false).await;
+    let result = api!(upload_check_name(guard, "chunk.txt".to_string(), root, false));
     crate::assert_error::<_, wlist_native::common::exceptions::StorageNotFoundError>(result)?;
-    let result = upload_check_name(c!(guard), "chunk.txt".to_string(), root, true).await;
+    let result = api!(upload_check_name(guard, "chunk.txt".to_string(), root, true));
     crate::assert_error::<_, wlist_native::common::exceptions::StorageNotFoundError>(result)?;
 
     Ok(())
 }
 
 pub async fn check_name(guard: &InitializeGuard, name: String, parent: FileLocation, is_directory: bool) -> anyhow::Result<Option<()>> {
-    let result = upload_check_name(c!(guard), name.to_string(), parent, is_directory).await;
+    let result = api!(upload_check_name(guard, name.to_string(), parent, is_directory));
     if let Err(e) = &result {
         macro_rules! downcast_ref {
             ($e: ident, $t: ty) => {
@@ -48,7 +57,7 @@ pub async fn check_name(guard: &InitializeGuard, name: String, parent: FileLocat
 }
 
 pub async fn test_normal(guard: &InitializeGuard, root: FileLocation) -> anyhow::Result<()> {
-    upload_check_name(c!(guard), "hello.txt".to_string(), root, false).await?;
+    api!(upload_check_name(guard, "hello.txt".to_string(), root, false))?;
 
     // test_duplicate
     let result = check_name(guard, "chunk.txt".to_string(), root, false).await;
@@ -59,9 +68,208 @@ pub async fn test_normal(guard: &InitializeGuard, root: FileLocation) -> anyhow:
     crate::assert_error_option::<_, wlist_native::common::exceptions::DuplicateFileError>(result)?;
     let result = check_name(guard, "hello".to_string(), root, true).await;
     crate::assert_error_option::<_, wlist_native::common::exceptions::DuplicateFileError>(result)?;
+
+    test_corpus(guard, root).await?;
+    test_collision(guard, root).await
+}
+
+/// Names checked against every storage, keyed by an ascii label.
+fn corpus() -> Vec<(&'static str, String)> {
+    vec![
+        ("plain", "corpus.txt".to_string()),
+        // reserved characters
+        ("less", "a<b.txt".to_string()),
+        ("greater", "a>b.txt".to_string()),
+        ("colon", "a:b.txt".to_string()),
+        ("quote", "a\"b.txt".to_string()),
+        ("pipe", "a|b.txt".to_string()),
+        ("question", "a?b.txt".to_string()),
+        ("asterisk", "a*b.txt".to_string()),
+        ("slash", "a/b.txt".to_string()),
+        ("backslash", "a\\b.txt".to_string()),
+        // control characters
+        ("nul", "a\0b.txt".to_string()),
+        ("tab", "a\tb.txt".to_string()),
+        ("newline", "a\nb.txt".to_string()),
+        ("delete", "a\x7fb.txt".to_string()),
+        // dots and spaces
+        ("trailing_dot", "corpus.txt.".to_string()),
+        ("trailing_space", "corpus.txt ".to_string()),
+        ("leading_space", " corpus.txt".to_string()),
+        ("dot", ".".to_string()),
+        ("dot_dot", "..".to_string()),
+        // windows reserved names
+        ("con", "CON".to_string()),
+        ("con_suffix", "con.txt".to_string()),
+        ("nul_suffix", "NUL.txt".to_string()),
+        ("aux", "aux".to_string()),
+        ("com1", "COM1.txt".to_string()),
+        ("lpt9", "LPT9.txt".to_string()),
+        // unicode variants
+        ("nfc", "caf\u{e9}.txt".to_string()),
+        ("nfd", "cafe\u{301}.txt".to_string()),
+        ("fullwidth", "\u{ff43}\u{ff4f}\u{ff52}\u{ff50}\u{ff55}\u{ff53}.txt".to_string()),
+        ("zero_width", "cor\u{200b}pus.txt".to_string()),
+        ("right_to_left_override", "\u{202e}txt.exe".to_string()),
+        // char length versus byte length
+        ("ascii_255", "a".repeat(251) + ".txt"),
+        ("ascii_256", "a".repeat(252) + ".txt"),
+        ("cjk_253_bytes", "中".repeat(83) + ".txt"),
+        ("cjk_259_bytes", "中".repeat(85) + ".txt"),
+        ("emoji_252_bytes", "😀".repeat(62) + ".txt"),
+        ("emoji_260_bytes", "😀".repeat(64) + ".txt"),
+        // argument limits
+        ("empty", String::new()),
+        ("max", "a".repeat(32763) + ".txt"),
+        ("over_max", "a".repeat(32764) + ".txt"),
+    ]
+}
+
+async fn verdict(guard: &InitializeGuard, name: String, parent: FileLocation) -> anyhow::Result<&'static str> {
+    let Err(e) = api!(upload_check_name(guard, name, parent, false)) else { return Ok("ok") };
+    macro_rules! verdict {
+        ($($t: ty => $v: literal),*) => {
+            $(if e.downcast_ref::<$t>().is_some() { return Ok($v); })*
+        };
+    }
+    verdict!(
+        wlist_native::common::exceptions::NameTooLongError => "too_long",
+        wlist_native::common::exceptions::InvalidFilenameError => "invalid",
+        wlist_native::common::exceptions::IllegalSuffixError => "illegal_suffix",
+        wlist_native::common::exceptions::IncorrectArgumentError => "incorrect_argument"
+    );
+    Err(e)
+}
+
+/// Compares each storage's verdicts on [corpus] with `check_name.toml`.
+/// The observed table is always written to `run/check_name/` to review and check in.
+/// Only the checked in labels are compared, and a storage without a section is only recorded.
+async fn test_corpus(guard: &InitializeGuard, root: FileLocation) -> anyhow::Result<()> {
+    let storage = api!(storages_get(guard, root.storage, false))?.basic.storage_type;
+    let mut tables = toml::from_str::<BTreeMap<String, BTreeMap<String, String>>>(include_str!("check_name.toml"))?;
+    let section = format!("{storage:?}");
+    let mut expected = tables.remove(&section);
+
+    let mut observed = String::new();
+    let mut mismatches = Vec::new();
+    for (label, name) in corpus() {
+        let verdict = verdict(guard, name, root).await?;
+        observed.push_str(&format!("{label} = \"{verdict}\"\n"));
+        if let Some(expected) = expected.as_mut().and_then(|e| e.remove(label)) {
+            if expected != verdict {
+                mismatches.push(format!("{label}: expected {expected:?}, got {verdict:?}"));
+            }
+        }
+    }
+    if let Some(unknown) = expected.as_ref().filter(|e| !e.is_empty()) {
+        mismatches.push(format!("labels not in the corpus: {:?}", unknown.keys().collect::<Vec<_>>()));
+    }
+    std::fs::create_dir_all("run/check_name")?;
+    let path = format!("run/check_name/{section}.toml");
+    std::fs::write(&path, format!("[{section}]\n{observed}"))?;
+    if expected.is_none() {
+        tracing::warn!(%section, %path, "No check_name verdicts are checked in, recorded only.");
+        return Ok(());
+    }
+    if !mismatches.is_empty() {
+        anyhow::bail!("{section} check_name verdicts differ from check_name.toml (observed table written to {path}):\n{}", mismatches.join("\n"));
+    }
     Ok(())
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Fold {
+    /// Names differ only by ascii case.
+    Case,
+    /// Names differ only by NFC versus NFD form.
+    Normalization,
+}
+
+/// Which folds make two names collide on a storage.
+struct Rule {
+    folds: &'static [Fold],
+    /// Whether the rule is recorded from a live run. Mismatches of provisional rules are only logged.
+    verified: bool,
+}
+
+fn rule(storage: StorageType) -> Rule {
+    match storage {
+        // The mocker runs offline, so it is checked strictly: names must only collide byte for byte.
+        StorageType::Mocker => Rule { folds: &[], verified: true },
+        StorageType::Lanzou => Rule { folds: &[], verified: false },
+        StorageType::Baidu => Rule { folds: &[Fold::Case], verified: false },
+        StorageType::Pan123 => Rule { folds: &[], verified: false },
+    }
+}
+
+/// (existing name, new name, is directory, fold between them)
+static COLLISIONS: &[(&str, &str, bool, Fold)] = &[
+    ("Case.txt", "case.txt", false, Fold::Case),
+    ("caf\u{e9}.txt", "cafe\u{301}.txt", false, Fold::Normalization),
+    ("Folder", "FOLDER", true, Fold::Case),
+    ("\u{e9}t\u{e9}", "e\u{301}te\u{301}", true, Fold::Normalization),
+];
+
+async fn remove(guard: &InitializeGuard, location: FileLocation) -> anyhow::Result<()> {
+    let information = api!(trash_trash(guard, location))?;
+    api!(trash_delete(guard, information.get_location(location.storage)))
+}
+
+/// Returns the value, or `None` if the call is rejected as a duplicate.
+fn collided<T>(result: anyhow::Result<T>) -> anyhow::Result<Option<T>> {
+    crate::may_error::<_, wlist_native::common::exceptions::DuplicateFileError>(result)
+}
+
+fn expect(rule: &Rule, expected: bool, collided: bool, context: impl FnOnce() -> String) -> anyhow::Result<()> {
+    if expected == collided { return Ok(()); }
+    let context = context();
+    if !rule.verified {
+        tracing::warn!(%context, %expected, %collided, "Provisional collision rule differs.");
+        return Ok(());
+    }
+    anyhow::bail!("{context}: expected collision {expected}, got {collided}")
+}
+
+async fn test_collision(guard: &InitializeGuard, root: FileLocation) -> anyhow::Result<()> {
+    let storage = api!(storages_get(guard, root.storage, false))?.basic.storage_type;
+    let rule = rule(storage);
+    let directory = api!(upload_mkdir(guard, root, "collision".to_string(), Duplicate::Error))?;
+    let directory = directory.get_location(root.storage);
+    for (i, &(existing, name, is_directory, fold)) in COLLISIONS.iter().enumerate() {
+        let expected = rule.folds.contains(&fold);
+        let create = |name: &str| {
+            let name = name.to_string();
+            async move { anyhow::Ok(if is_directory {
+                api!(upload_mkdir(guard, directory, name, Duplicate::Error))?
+            } else {
+                super::upload::upload(guard, directory, name, Bytes::from_static(b"collision"), Duplicate::Error).await?
+            }) }
+        };
+        create(existing).await?;
+
+        let result = api!(upload_check_name(guard, name.to_string(), directory, is_directory));
+        let checked = collided(result)?;
+        expect(&rule, expected, checked.is_none(), || format!("{storage:?} upload_check_name {name:?} over {existing:?}"))?;
+
+        let other = create(&format!("rename-{i}{}", if is_directory { "" } else { ".txt" })).await?.get_location(root.storage);
+        let result = api!(files_rename(guard, other, name.to_string(), Duplicate::Error));
+        let renamed = collided(result)?;
+        expect(&rule, expected, renamed.is_none(), || format!("{storage:?} files_rename {name:?} over {existing:?}"))?;
+        // A renamed directory may get a new id (see the client module).
+        remove(guard, renamed.map(|information| information.get_location(root.storage)).unwrap_or(other)).await?;
+
+        let created = collided(create(name).await)?;
+        expect(&rule, expected, created.is_none(), || format!("{storage:?} create {name:?} over {existing:?}"))?;
+        if let Some(created) = created {
+            let list = super::list::list(guard, directory, None).await?;
+            assert!(list.files.iter().any(|f| f.name.as_str() == existing), "{storage:?}: {existing:?} is lost: {list:?}");
+            assert!(list.files.iter().any(|f| f.name.as_str() == name), "{storage:?}: {name:?} is renamed: {list:?}");
+            remove(guard, created.get_location(root.storage)).await?;
+        }
+    }
+    remove(guard, directory).await
+}
+
 pub async fn test_empty(guard: &InitializeGuard, root: FileLocation) -> anyhow::Result<()> {
     check_name(guard, "a".to_string(), root, false).await?;
     check_name(guard, "a".repeat(32767), root, false).await?;
@@ -70,7 +278,7 @@ pub async fn test_empty(guard: &InitializeGuard, root: FileLocation) -> anyhow::
     check_name(guard, "中文测试".to_string(), root, true).await?;
     check_name(guard, "123//".to_string(), root, true).await?;
 
-    upload_check_name(c!(guard), "1.txt".to_string(), root, false).await?;
-    upload_check_name(c!(guard), "a.zip".to_string(), root, false).await?;
+    api!(upload_check_name(guard, "1.txt".to_string(), root, false))?;
+    api!(upload_check_name(guard, "a.zip".to_string(), root, false))?;
     Ok(())
 }
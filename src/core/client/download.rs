@@ -1,11 +1,12 @@
 use std::cmp::min;
+use std::net::SocketAddr;
 use std::sync::Arc;
 use std::time::Duration;
 
 use anyhow::Error;
 use bytes::{Buf, BufMut, Bytes, BytesMut};
-use dashmap::DashMap;
 use tokio::sync::watch::channel;
+use tokio::sync::Barrier;
 use tokio::task::{yield_now, JoinSet};
 use tokio::time::sleep;
 use tracing::debug;
@@ -13,46 +14,47 @@ use tracing::debug;
 use wlist_native::common::data::files::tokens::DownloadToken;
 use wlist_native::common::data::files::FileLocation;
 use wlist_native::core::client::download::{download_cancel, download_confirm, download_finish, download_request, download_stream};
+use wlist_native::core::client::users::users_login;
+use wlist_native::core::client::WlistClientManager;
+use wlist_native::core::server::WlistServer;
 
-use crate::core::{c, InitializeGuard};
+use crate::core::{api, scoped, InitializeGuard};
 
 pub async fn test_none(guard: &InitializeGuard) -> anyhow::Result<()> {
     let file = FileLocation { storage: 0, file_id: 0, is_directory: false, };
     let root = FileLocation { storage: 0, file_id: 0, is_directory: true, };
 
-    let result = download_request(c!(guard), file, 0, u64::MAX).await;
+    let result = api!(download_request(guard, file, 0, u64::MAX));
     crate::assert_error::<_, wlist_native::common::exceptions::StorageNotFoundError>(result)?;
-    let result = download_request(c!(guard), root, 0, u64::MAX).await;
+    let result = api!(download_request(guard, root, 0, u64::MAX));
     crate::assert_error::<_, wlist_native::common::exceptions::IncorrectArgumentError>(result)?;
-    let result = download_request(c!(guard), file, 0, 0).await;
+    let result = api!(download_request(guard, file, 0, 0));
     crate::assert_error::<_, wlist_native::common::exceptions::StorageNotFoundError>(result)?;
-    let result = download_request(c!(guard), file, 1, 0).await;
+    let result = api!(download_request(guard, file, 1, 0));
     crate::assert_error::<_, wlist_native::common::exceptions::IncorrectArgumentError>(result)?;
     Ok(())
 }
 
 pub async fn download0(guard: &InitializeGuard, token: &DownloadToken) -> anyhow::Result<(Bytes, u64, u64)> {
-    let information = download_confirm(c!(guard), token.clone()).await?;
+    let information = api!(download_confirm(guard, token.clone()))?;
     if information.chunks.is_empty() {
         return Ok((Bytes::new(), 0, 0));
     }
-    let map = Arc::new(DashMap::new());
-    let mut set = JoinSet::new();
-    for (chunk, id) in information.chunks.iter().zip(0..) {
-        let guard = unsafe { &*(guard as *const InitializeGuard) }; // Safety: not cancelled.
+    let tracker = &crate::Tracker::new(token);
+    let buffers = scoped(information.chunks.iter().zip(0..).map(|(chunk, id)| {
         let token = token.clone();
-        let map = Arc::clone(&map);
         let chunk = *chunk;
-        set.spawn(async move {
+        async move {
             let buffer = if chunk.range {
                 let (tx, mut rx) = channel(0);
                 let mut buffer = BytesMut::new().limit(chunk.size as usize);
                 tokio::select! {
-                    r = async { download_stream(c!(guard), token, id, 0, &mut buffer, tx, channel(true).1).await } => r?,
+                    r = async { api!(download_stream(guard, token.clone(), id, 0, &mut buffer, tx, channel(true).1)) } => r?,
                     _ = async { loop {
                         if rx.changed().await.is_ok() {
                             let transferred_bytes = *rx.borrow_and_update();
                             debug!(%transferred_bytes, "Downloading with range.");
+                            tracker.update(format_args!("chunk {id}: {transferred_bytes} bytes"));
                             sleep(Duration::from_millis(50)).await;
                         }
                         yield_now().await
@@ -70,11 +72,12 @@ pub async fn download0(guard: &InitializeGuard, token: &DownloadToken) -> anyhow
                     let mut buf = BytesMut::new().limit(chunk_size);
                     let (tx, mut rx) = channel(0);
                     tokio::select! {
-                        r = async { download_stream(c!(guard), token.clone(), id, 0, &mut buf, tx, channel(true).1).await } => r?,
+                        r = async { api!(download_stream(guard, token.clone(), id, 0, &mut buf, tx, channel(true).1)) } => r?,
                         _ = async { loop {
                             if rx.changed().await.is_ok() {
                                 let transferred_bytes = *rx.borrow_and_update();
                                 debug!(%transferred_bytes, %chunk_size, "Downloading.");
+                                tracker.update(format_args!("chunk {id}: {} + {transferred_bytes} bytes", buffer.len()));
                                 sleep(Duration::from_millis(50)).await;
                             }
                             yield_now().await
@@ -87,19 +90,15 @@ pub async fn download0(guard: &InitializeGuard, token: &DownloadToken) -> anyhow
                     }
                 }
             };
-            map.insert(id, buffer);
-            Ok::<_, Error>(())
-        });
-    }
-    for r in set.join_all().await { r?; }
-    download_finish(c!(guard), token.clone()).await?;
-    let map = Arc::try_unwrap(map).unwrap();
+            Ok::<_, Error>(buffer)
+        }
+    })).await?;
+    api!(download_finish(guard, token.clone()))?;
     let mut buffer = BytesMut::new();
     let l = information.chunks[0].start;
     let mut r = l;
-    for (chunk, id) in information.chunks.iter().zip(0..) {
+    for (chunk, buf) in information.chunks.iter().zip(buffers) {
         assert_eq!(r, chunk.start);
-        let buf = map.remove(&id).unwrap().1;
         r += buf.remaining() as u64;
         buffer.put_slice(&buf);
     }
@@ -108,6 +107,44 @@ pub async fn download0(guard: &InitializeGuard, token: &DownloadToken) -> anyhow
     Ok((buffer, l, r))
 }
 
+/// Checks `download_request` over ranges at the start, middle, end and beyond EOF of `content`.
+///
+/// Storages may round a range outward, so rather than expecting the exact range,
+/// the returned `[l, r)` must cover the requested bytes and match `content[l..r]`.
+async fn test_range_matrix(guard: &InitializeGuard, location: FileLocation, content: &[u8]) -> anyhow::Result<()> {
+    let n = content.len() as u64;
+    let middle = n / 2;
+    let last = n.saturating_sub(1);
+    let ranges = [
+        (0, u64::MAX), (0, 0), (0, 31), (1, 1),
+        (middle, middle), (middle, middle + 99), (middle, u64::MAX),
+        (last, last), (last, u64::MAX), (n.saturating_sub(32), last), (n.saturating_sub(32), n + 100),
+        (n, n), (n, u64::MAX), (n + 10, n + 20), (n + 10, u64::MAX),
+    ];
+    for (from, to) in ranges {
+        let result = api!(download_request(guard, location, from, to));
+        let confirmation = if from >= n {
+            // Beyond EOF: either rejected, or nothing but (rounded) file content is returned.
+            match crate::may_error::<_, wlist_native::common::exceptions::IncorrectArgumentError>(result)? {
+                Some(confirmation) => confirmation, None => continue,
+            }
+        } else {
+            result?
+        };
+        let (bytes, l, r) = download0(guard, &confirmation.token).await?;
+        assert_eq!(confirmation.size, r - l, "range ({from}, {to})");
+        assert!(l <= r && r <= n, "range ({from}, {to}) returned [{l}, {r}) of {n}");
+        assert_eq!(bytes, &content[l as usize..r as usize], "range ({from}, {to}) returned [{l}, {r})");
+        if from < n {
+            assert!(l <= from && r > min(to, last), "range ({from}, {to}) returned [{l}, {r})");
+        }
+        if l != from {
+            debug!(%from, %to, %l, %r, "Range was rounded.");
+        }
+    }
+    Ok(())
+}
+
 pub async fn test_normal(guard: &InitializeGuard, root: FileLocation) -> anyhow::Result<()> {
     let list = super::list::list(guard, root, None).await?;
     let chunk = FileLocation { storage: root.storage, file_id: list.files[0].id, is_directory: false, };
@@ -116,7 +153,7 @@ pub async fn test_normal(guard: &InitializeGuard, root: FileLocation) -> anyhow:
     tokio::try_join!(
         async {
             // download_test_chunk
-            let confirmation = download_request(c!(guard), chunk, 0, u64::MAX).await?;
+            let confirmation = api!(download_request(guard, chunk, 0, u64::MAX))?;
             let (bytes, l, r) = download0(guard, &confirmation.token).await?;
             assert_eq!(l, 0); assert_eq!(r, 4 << 10);
             assert_eq!(bytes, "@wlist small chunk 32 origin len".repeat(128).as_bytes());
@@ -124,7 +161,7 @@ pub async fn test_normal(guard: &InitializeGuard, root: FileLocation) -> anyhow:
         },
         async {
             // download_test_large
-            let confirmation = download_request(c!(guard), large, 0, u64::MAX).await?;
+            let confirmation = api!(download_request(guard, large, 0, u64::MAX))?;
             let (bytes, l, r) = download0(guard, &confirmation.token).await?;
             assert_eq!(l, 0); assert_eq!(r, 12 << 20);
             assert_eq!(bytes, "@wlist large file 32 origin len\n".repeat(393216).as_bytes());
@@ -132,7 +169,7 @@ pub async fn test_normal(guard: &InitializeGuard, root: FileLocation) -> anyhow:
         },
         async {
             // download_test_range
-            let confirmation = download_request(c!(guard), chunk, 0, 31).await?;
+            let confirmation = api!(download_request(guard, chunk, 0, 31))?;
             let (bytes, l, r) = download0(guard, &confirmation.token).await?;
             assert_eq!(l, 0); assert!(r > 31);
             assert_eq!(&bytes[..32], b"@wlist small chunk 32 origin len");
@@ -140,7 +177,7 @@ pub async fn test_normal(guard: &InitializeGuard, root: FileLocation) -> anyhow:
         },
         async {
             // download_test_range_no_head
-            let confirmation = download_request(c!(guard), chunk, 1, 1).await?;
+            let confirmation = api!(download_request(guard, chunk, 1, 1))?;
             let (bytes, l, r) = download0(guard, &confirmation.token).await?;
             assert!(l <= 1); assert!(r > 1);
             if l == 0 {
@@ -152,13 +189,13 @@ pub async fn test_normal(guard: &InitializeGuard, root: FileLocation) -> anyhow:
         },
         async {
             // download_test_cancel
-            let confirmation = download_request(c!(guard), chunk, 0, 0).await?;
-            let result = download_stream(c!(guard), confirmation.token.clone(), 0, 0,
-                                         &mut BytesMut::new().limit(0), channel(0).0, channel(true).1).await;
+            let confirmation = api!(download_request(guard, chunk, 0, 0))?;
+            let result = api!(download_stream(guard, confirmation.token.clone(), 0, 0,
+                                         &mut BytesMut::new().limit(0), channel(0).0, channel(true).1));
             crate::assert_error::<_, wlist_native::common::exceptions::TokenExpiredError>(result)?;
-            let result = download_finish(c!(guard), confirmation.token.clone()).await;
+            let result = api!(download_finish(guard, confirmation.token.clone()));
             crate::assert_error::<_, wlist_native::common::exceptions::TokenExpiredError>(result)?;
-            download_cancel(c!(guard), confirmation.token).await
+            api!(download_cancel(guard, confirmation.token))
         },
     )?;
 
@@ -169,18 +206,93 @@ pub async fn test_normal(guard: &InitializeGuard, root: FileLocation) -> anyhow:
 
     if let Some(empty) = empty {
         // download_test_empty
-        let confirmation = download_request(c!(guard), empty, 0, u64::MAX).await?;
+        let confirmation = api!(download_request(guard, empty, 0, u64::MAX))?;
         let (bytes, l, r) = download0(guard, &confirmation.token).await?;
         assert_eq!(l, 0); assert_eq!(r, 0); assert_eq!(bytes, "");
     }
 
+    // download_test_range_matrix
+    test_range_matrix(guard, chunk, "@wlist small chunk 32 origin len".repeat(128).as_bytes()).await?;
+    test_range_matrix(guard, large, "@wlist large file 32 origin len\n".repeat(393216).as_bytes()).await?;
+    if let Some(empty) = empty {
+        test_range_matrix(guard, empty, b"").await?;
+    }
+
     // TODO: test pause
 
     Ok(())
 }
 
+const CONCURRENT_TOKENS: usize = 12;
+
+/// Downloads (part of) `content` on its own client. Every fourth token is cancelled after the first piece.
+async fn download_concurrently(address: SocketAddr, password: &'static str, barrier: Arc<Barrier>, location: FileLocation, content: Bytes, index: usize) -> anyhow::Result<()> {
+    let manager = WlistClientManager::new(address).await?;
+    let mut client = manager.get().await?;
+    let mut client = Some(&mut client);
+    let client = &mut client;
+    users_login(client, "admin".to_string(), password.to_string()).await?;
+
+    let n = content.len() as u64;
+    let (from, to) = match index % 3 {
+        0 => (0, u64::MAX),
+        1 => (index as u64 * 1000, index as u64 * 1000 + (1 << 20)),
+        _ => (n / 2, u64::MAX),
+    };
+    let cancel = index % 4 == 3;
+    crate::core::rendezvous(&barrier).await?;
+
+    let confirmation = api!(@client download_request(client, location, from, to))?;
+    let information = api!(@client download_confirm(client, confirmation.token.clone()))?;
+    let mut buffer = BytesMut::new();
+    for (chunk, id) in information.chunks.iter().zip(0..) {
+        assert_eq!(information.chunks[0].start + buffer.len() as u64, chunk.start);
+        let mut done = 0;
+        while done < chunk.size {
+            let size = min(1 << 16, chunk.size - done) as usize;
+            let mut buf = BytesMut::new().limit(size);
+            let start = if chunk.range { done } else { 0 };
+            api!(@client download_stream(client, confirmation.token.clone(), id, start, &mut buf, channel(0).0, channel(true).1))?;
+            let buf = buf.into_inner();
+            done += buf.len() as u64;
+            buffer.put_slice(&buf);
+            if cancel {
+                api!(@client download_cancel(client, confirmation.token.clone()))?;
+                let result = api!(@client download_stream(client, confirmation.token.clone(), id, 0, &mut BytesMut::new().limit(1), channel(0).0, channel(true).1));
+                crate::assert_error::<_, wlist_native::common::exceptions::TokenExpiredError>(result)?;
+                return Ok(());
+            }
+            if buf.len() < size { break; }
+        }
+    }
+    api!(@client download_finish(client, confirmation.token))?;
+
+    let l = information.chunks.first().map(|c| c.start).unwrap_or(0);
+    let r = l + buffer.len() as u64;
+    assert_eq!(confirmation.size, r - l, "token {index}");
+    assert!(l <= from && r > min(to, n - 1) && r <= n, "token {index}: range ({from}, {to}) returned [{l}, {r})");
+    assert_eq!(buffer, &content[l as usize..r as usize], "token {index}: [{l}, {r}) mismatched");
+    Ok(())
+}
+
+pub async fn test_concurrent(guard: &InitializeGuard, root: FileLocation) -> anyhow::Result<()> {
+    let list = super::list::list(guard, root, None).await?;
+    let large = FileLocation { storage: root.storage, file_id: list.files[3].id, is_directory: false, };
+    let content = Bytes::from("@wlist large file 32 origin len\n".repeat(393216));
+
+    let server = WlistServer::start("localhost:0").await?;
+    let barrier = Arc::new(Barrier::new(CONCURRENT_TOKENS));
+    let mut set = JoinSet::new();
+    for index in 0..CONCURRENT_TOKENS {
+        set.spawn(crate::in_current_test(download_concurrently(server.local_addr(), guard.password, Arc::clone(&barrier), large, content.clone(), index)));
+    }
+    for r in set.join_all().await { r?; }
+    server.stop().await?;
+    Ok(())
+}
+
 pub async fn test_empty(guard: &InitializeGuard, root: FileLocation) -> anyhow::Result<()> {
-    let result = download_request(c!(guard), FileLocation { storage: root.storage, file_id: 0, is_directory: false, }, 0, u64::MAX).await;
+    let result = api!(download_request(guard, FileLocation { storage: root.storage, file_id: 0, is_directory: false, }, 0, u64::MAX));
     crate::assert_error::<_, wlist_native::common::exceptions::FileNotFoundError>(result)?;
     Ok(())
 }
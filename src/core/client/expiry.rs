@@ -0,0 +1,103 @@
+use std::cmp::min;
+use std::time::Duration;
+
+use bytes::{BufMut, BytesMut};
+use tokio::sync::watch::channel;
+use tokio::time::sleep;
+use tracing::info;
+
+use wlist_native::common::data::files::options::Duplicate;
+use wlist_native::common::data::files::FileLocation;
+use wlist_native::core::client::download::{download_cancel, download_confirm, download_finish, download_request, download_stream};
+use wlist_native::core::client::refresh::{refresh_cancel, refresh_confirm, refresh_request};
+use wlist_native::core::client::upload::{upload_cancel, upload_confirm, upload_request, upload_stream};
+
+use crate::core::{api, InitializeGuard};
+
+/// How long the server keeps an idle token, in seconds from `WLIST_TEST_TOKEN_EXPIRY`.
+/// wlist_native neither exports its expiry nor has a clock hook to shorten it, so the test idles in real time.
+/// The five minutes default is an assumption, not taken from wlist_native: set the variable to the expiry of the server under test.
+fn token_expiry() -> Duration {
+    crate::timeout_from_env("WLIST_TEST_TOKEN_EXPIRY", Duration::from_secs(5 * 60))
+}
+const MARGIN: Duration = Duration::from_secs(10);
+const PIECE: u64 = 64 << 10;
+
+async fn test_idle(guard: &InitializeGuard, root: FileLocation, large: FileLocation) -> anyhow::Result<()> {
+    let refresh = api!(refresh_request(guard, root))?.token;
+    let md5 = super::upload::generate_md5();
+    let upload = api!(upload_request(guard, root, "Expiry.txt".to_string(), 5, md5.clone(), Some(vec![md5]), Duplicate::Error))?;
+    assert_eq!(upload.done, false);
+    let upload = upload.token;
+    let md5 = super::upload::generate_md5();
+    let upload_confirmed = api!(upload_request(guard, root, "ExpiryConfirmed.txt".to_string(), 5, md5.clone(), Some(vec![md5]), Duplicate::Error))?;
+    assert_eq!(upload_confirmed.done, false);
+    let upload_confirmed = upload_confirmed.token;
+    api!(upload_confirm(guard, upload_confirmed.clone()))?;
+    let download = api!(download_request(guard, large, 0, u64::MAX))?.token;
+    let download_confirmed = api!(download_request(guard, large, 0, u64::MAX))?.token;
+    api!(download_confirm(guard, download_confirmed.clone()))?;
+
+    sleep(token_expiry() + MARGIN).await;
+
+    let result = api!(refresh_confirm(guard, refresh.clone()));
+    crate::assert_error::<_, wlist_native::common::exceptions::TokenExpiredError>(result)?;
+    let result = api!(refresh_cancel(guard, refresh));
+    crate::may_error::<_, wlist_native::common::exceptions::TokenExpiredError>(result)?;
+
+    let result = api!(upload_confirm(guard, upload.clone()));
+    crate::assert_error::<_, wlist_native::common::exceptions::TokenExpiredError>(result)?;
+    let result = api!(upload_cancel(guard, upload));
+    crate::may_error::<_, wlist_native::common::exceptions::TokenExpiredError>(result)?;
+    let result = api!(upload_stream(guard, upload_confirmed.clone(), 0, &mut &b"hello"[..], channel(0).0, channel(true).1));
+    crate::assert_error::<_, wlist_native::common::exceptions::TokenExpiredError>(result)?;
+    let result = api!(upload_cancel(guard, upload_confirmed));
+    crate::may_error::<_, wlist_native::common::exceptions::TokenExpiredError>(result)?;
+
+    let result = api!(download_confirm(guard, download.clone()));
+    crate::assert_error::<_, wlist_native::common::exceptions::TokenExpiredError>(result)?;
+    let result = api!(download_cancel(guard, download));
+    crate::may_error::<_, wlist_native::common::exceptions::TokenExpiredError>(result)?;
+    let result = api!(download_stream(guard, download_confirmed.clone(), 0, 0, &mut BytesMut::new().limit(1), channel(0).0, channel(true).1));
+    crate::assert_error::<_, wlist_native::common::exceptions::TokenExpiredError>(result)?;
+    let result = api!(download_cancel(guard, download_confirmed));
+    crate::may_error::<_, wlist_native::common::exceptions::TokenExpiredError>(result)?;
+    Ok(())
+}
+
+/// Streams `large` slowly enough that the whole transfer outlives [token_expiry].
+async fn test_active(guard: &InitializeGuard, large: FileLocation) -> anyhow::Result<()> {
+    let confirmation = api!(download_request(guard, large, 0, u64::MAX))?;
+    let information = api!(download_confirm(guard, confirmation.token.clone()))?;
+    let pieces = confirmation.size.div_ceil(PIECE).max(1);
+    let pace = (token_expiry() + MARGIN) * 2 / pieces as u32;
+    let mut buffer = BytesMut::new();
+    for (chunk, id) in information.chunks.iter().zip(0..) {
+        let mut done = 0;
+        while done < chunk.size {
+            let size = min(PIECE, chunk.size - done) as usize;
+            let mut buf = BytesMut::new().limit(size);
+            let start = if chunk.range { done } else { 0 };
+            api!(download_stream(guard, confirmation.token.clone(), id, start, &mut buf, channel(0).0, channel(true).1))?;
+            let buf = buf.into_inner();
+            done += buf.len() as u64;
+            buffer.put_slice(&buf);
+            if buf.len() < size { break; }
+            sleep(pace).await;
+        }
+    }
+    api!(download_finish(guard, confirmation.token))?;
+    assert_eq!(buffer, "@wlist large file 32 origin len\n".repeat(393216).as_bytes());
+    Ok(())
+}
+
+pub async fn test_normal(guard: &InitializeGuard, root: FileLocation) -> anyhow::Result<()> {
+    let list = super::list::list(guard, root, None).await?;
+    let large = FileLocation { storage: root.storage, file_id: list.files[3].id, is_directory: false, };
+    info!(expiry = ?token_expiry(), "Idling tokens past their expiry.");
+    tokio::try_join!(
+        test_idle(guard, root, large),
+        test_active(guard, large),
+    )?;
+    Ok(())
+}
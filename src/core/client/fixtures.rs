@@ -0,0 +1,156 @@
+use std::collections::BTreeSet;
+use std::io::Cursor;
+
+use bytes::Bytes;
+use image::{ImageFormat, RgbImage};
+use indexmap::IndexMap;
+
+use wlist_native::common::data::files::options::{Duplicate, FilesFilter, FilesOrder, ListFileOptions};
+use wlist_native::common::data::files::FileLocation;
+use wlist_native::common::data::Direction;
+use wlist_native::core::client::files::files_get;
+use wlist_native::core::client::trash::{trash_delete, trash_trash};
+use wlist_native::core::client::upload::upload_mkdir;
+
+use crate::core::{api, InitializeGuard};
+
+pub const WIDTH: u32 = 320;
+pub const HEIGHT: u32 = 240;
+
+/// A generated file to upload.
+pub struct Fixture {
+    pub name: &'static str,
+    pub data: Bytes,
+    /// Whether the content is an image or video the server may make a thumbnail for.
+    pub media: bool,
+}
+
+pub fn frame(seed: u32) -> RgbImage {
+    RgbImage::from_fn(WIDTH, HEIGHT, |x, y| image::Rgb([(x + seed) as u8, (y + seed) as u8, ((x ^ y) + seed) as u8]))
+}
+
+pub fn encode(image: &RgbImage, format: ImageFormat) -> anyhow::Result<Bytes> {
+    let mut buffer = Cursor::new(Vec::new());
+    image.write_to(&mut buffer, format)?;
+    Ok(Bytes::from(buffer.into_inner()))
+}
+
+fn riff(id: &[u8; 4], data: &[u8]) -> Vec<u8> {
+    let mut chunk = Vec::with_capacity(data.len() + 9);
+    chunk.extend_from_slice(id);
+    chunk.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    chunk.extend_from_slice(data);
+    if data.len() % 2 == 1 { chunk.push(0); }
+    chunk
+}
+
+fn riff_list(kind: &[u8; 4], children: &[Vec<u8>]) -> Vec<u8> {
+    let mut data = kind.to_vec();
+    for child in children { data.extend_from_slice(child); }
+    riff(b"LIST", &data)
+}
+
+fn le(values: &[u32]) -> Vec<u8> {
+    values.iter().flat_map(|v| v.to_le_bytes()).collect()
+}
+
+/// A motion-jpeg AVI of `frames` frames at 10 fps.
+pub fn video(frames: u32) -> anyhow::Result<Bytes> {
+    let jpegs = (0..frames).map(|i| encode(&frame(i * 16), ImageFormat::Jpeg)).collect::<anyhow::Result<Vec<_>>>()?;
+    let largest = jpegs.iter().map(|jpeg| jpeg.len() as u32).max().unwrap_or(0);
+
+    let avih = riff(b"avih", &le(&[100_000, largest * 10, 0, 0x10, frames, 0, 1, largest, WIDTH, HEIGHT, 0, 0, 0, 0]));
+    let mut strh = b"vidsMJPG".to_vec();
+    strh.extend_from_slice(&le(&[0, 0, 0, 1, 10, 0, frames, largest, u32::MAX, 0]));
+    strh.extend_from_slice(&le(&[0, WIDTH | (HEIGHT << 16)]));
+    let strh = riff(b"strh", &strh);
+    let mut strf = le(&[40, WIDTH, HEIGHT, 1 | (24 << 16)]);
+    strf.extend_from_slice(b"MJPG");
+    strf.extend_from_slice(&le(&[WIDTH * HEIGHT * 3, 0, 0, 0, 0]));
+    let strf = riff(b"strf", &strf);
+    let hdrl = riff_list(b"hdrl", &[avih, riff_list(b"strl", &[strh, strf])]);
+
+    let frames = jpegs.iter().map(|jpeg| riff(b"00dc", jpeg)).collect::<Vec<_>>();
+    let mut index = Vec::new();
+    let mut offset = 4; // after the 'movi' fourcc
+    for (frame, jpeg) in frames.iter().zip(&jpegs) {
+        index.extend_from_slice(b"00dc");
+        index.extend_from_slice(&le(&[0x10, offset, jpeg.len() as u32]));
+        offset += frame.len() as u32;
+    }
+    let movi = riff_list(b"movi", &frames);
+
+    let mut avi = b"AVI ".to_vec();
+    avi.extend_from_slice(&hdrl);
+    avi.extend_from_slice(&movi);
+    avi.extend_from_slice(&riff(b"idx1", &index));
+    Ok(Bytes::from(riff(b"RIFF", &avi)))
+}
+
+/// Every generated fixture. Names are unique and ordered by the case they cover.
+pub fn fixtures() -> anyhow::Result<Vec<Fixture>> {
+    let text = |name, data: &'static str| Fixture { name, data: Bytes::from_static(data.as_bytes()), media: false };
+    Ok(vec![
+        // media
+        Fixture { name: "image.png", data: encode(&frame(0), ImageFormat::Png)?, media: true },
+        Fixture { name: "photo.jpg", data: encode(&frame(64), ImageFormat::Jpeg)?, media: true },
+        Fixture { name: "PHOTO.JPEG", data: encode(&frame(128), ImageFormat::Jpeg)?, media: true },
+        Fixture { name: "clip.avi", data: video(10)?, media: true },
+        // multiple dots
+        text("archive.tar.gz", "not really gzip"),
+        text("v1.2.3.release.notes.txt", "multiple dots"),
+        text("trailing.dot.", "trailing dot"),
+        // no suffix
+        text("README", "no suffix"),
+        text("Makefile", "all:\n"),
+        // hidden
+        text(".hidden", "dot-file"),
+        text(".config.toml", "key = \"value\"\n"),
+        // emoji and rtl
+        Fixture { name: "😀 smile.png", data: encode(&frame(192), ImageFormat::Png)?, media: true },
+        text("🎉🎉.txt", "emoji"),
+        text("مرحبا بالعالم.txt", "arabic"),
+        text("שלום.md", "hebrew"),
+        text("mixed עברית english.txt", "bidirectional"),
+    ])
+}
+
+pub async fn test_normal(guard: &InitializeGuard, root: FileLocation) -> anyhow::Result<()> {
+    let directory = api!(upload_mkdir(guard, root, "fixtures".to_string(), Duplicate::Error))?;
+    let directory = directory.get_location(root.storage);
+    let fixtures = fixtures()?;
+    for fixture in &fixtures {
+        let information = super::upload::upload(guard, directory, fixture.name.to_string(), fixture.data.clone(), Duplicate::Error).await?;
+        assert_eq!(information.name.as_str(), fixture.name);
+        let details = api!(files_get(guard, information.get_location(root.storage), false, false))?;
+        let (md5, _) = super::upload::hash(&fixture.data, None).await;
+        super::get::assert_md5(Some(&md5), &details);
+        super::get::close_thumbnail(guard, &details).await?;
+    }
+
+    let list = super::list::list(guard, directory, Some(ListFileOptions {
+        filter: FilesFilter::Both, orders: IndexMap::from([(FilesOrder::Name, Direction::ASCEND)]), offset: 0, limit: fixtures.len() as _,
+    })).await?;
+    assert_eq!(list.total_file as usize, fixtures.len());
+    assert_eq!(list.total_directory, 0);
+    let listed = list.files.iter().map(|i| i.name.as_str()).collect::<BTreeSet<_>>();
+    let expected = fixtures.iter().map(|f| f.name).collect::<BTreeSet<_>>();
+    assert_eq!(listed, expected);
+
+    // order_test_suffix: the suffix is what follows the last dot, and is empty without one.
+    let list = super::list::list(guard, directory, Some(ListFileOptions {
+        filter: FilesFilter::Both, orders: IndexMap::from([(FilesOrder::Suffix, Direction::ASCEND), (FilesOrder::Name, Direction::DESCEND)]), offset: 0, limit: fixtures.len() as _,
+    })).await?;
+    assert_eq!(list.files.len(), fixtures.len());
+    let suffix = |name: &str| name.rsplit_once('.').map(|(_, suffix)| suffix.to_string()).unwrap_or_default();
+    for pair in list.files.windows(2) {
+        let (a, b) = (pair[0].name.as_str(), pair[1].name.as_str());
+        assert!(suffix(a) <= suffix(b), "{a:?} is ordered before {b:?} by suffix: {list:?}");
+        if suffix(a) == suffix(b) {
+            assert!(a > b, "{a:?} is ordered before {b:?} by name: {list:?}");
+        }
+    }
+
+    let information = api!(trash_trash(guard, directory))?;
+    api!(trash_delete(guard, information.get_location(root.storage)))
+}
@@ -3,19 +3,19 @@ use wlist_native::common::data::files::information::FileDetailsInformation;
 use wlist_native::core::client::download::download_cancel;
 use wlist_native::core::client::files::files_get;
 
-use crate::core::{c, InitializeGuard};
+use crate::core::{api, InitializeGuard};
 
 pub async fn test_none(guard: &InitializeGuard) -> anyhow::Result<()> {
     let file = FileLocation { storage: 0, file_id: 0, is_directory: true, };
     let directory = FileLocation { storage: 0, file_id: 0, is_directory: false, };
 
-    let result = files_get(c!(guard), file, true, false).await;
+    let result = api!(files_get(guard, file, true, false));
     crate::assert_error::<_, wlist_native::common::exceptions::StorageNotFoundError>(result)?;
-    let result = files_get(c!(guard), directory, true, false).await;
+    let result = api!(files_get(guard, directory, true, false));
     crate::assert_error::<_, wlist_native::common::exceptions::StorageNotFoundError>(result)?;
-    let result = files_get(c!(guard), file, true, true).await;
+    let result = api!(files_get(guard, file, true, true));
     crate::assert_error::<_, wlist_native::common::exceptions::StorageNotFoundError>(result)?;
-    let result = files_get(c!(guard), directory, true, true).await;
+    let result = api!(files_get(guard, directory, true, true));
     crate::assert_error::<_, wlist_native::common::exceptions::StorageNotFoundError>(result)?;
 
     Ok(())
@@ -24,7 +24,7 @@ pub async fn test_none(guard: &InitializeGuard) -> anyhow::Result<()> {
 #[allow(dead_code)]
 pub async fn get(guard: &InitializeGuard, parent: Option<i64>, location: FileLocation, check: bool) -> anyhow::Result<FileDetailsInformation> {
     loop {
-        let error = match files_get(c!(guard), location, false, check).await {
+        let error = match api!(files_get(guard, location, false, check)) {
             Ok(information) => break Ok(information), Err(error) => error,
         };
         if error.downcast_ref::<wlist_native::common::exceptions::FileNotFoundError>().is_some() {
@@ -50,34 +50,34 @@ pub fn assert_md5(expected: Option<&str>, information: &FileDetailsInformation)
 
 pub async fn close_thumbnail(guard: &InitializeGuard, information: &FileDetailsInformation) -> anyhow::Result<()> {
     if let Some(thumbnail) = information.thumbnail.as_ref() {
-        download_cancel(c!(guard), thumbnail.token.clone()).await?;
+        api!(download_cancel(guard, thumbnail.token.clone()))?;
     }
     Ok(())
 }
 
 pub async fn test_normal(guard: &InitializeGuard, root: FileLocation) -> anyhow::Result<()> {
-    let information = files_get(c!(guard), root, false, false).await?;
+    let information = api!(files_get(guard, root, false, false))?;
     assert_eq!(information.path, Vec::<String>::new());
     close_thumbnail(guard, &information).await?;
 
     let list = super::list::list(guard, root, None).await?;
 
     let location = FileLocation { storage: root.storage, file_id: list.files[0].id, is_directory: false, };
-    let information = files_get(c!(guard), location, false, false).await?;
+    let information = api!(files_get(guard, location, false, false))?;
     assert_eq!(information.basic.name.as_str(), "chunk.txt");
     assert_md5(Some("fc6cb96d6681a62e22a2bbd32e5e0519"), &information);
     assert_eq!(information.path, Vec::<String>::new());
     close_thumbnail(guard, &information).await?;
 
     let location = FileLocation { storage: root.storage, file_id: list.files[3].id, is_directory: false, };
-    let information = files_get(c!(guard), location, false, false).await?;
+    let information = api!(files_get(guard, location, false, false))?;
     assert_eq!(information.basic.name.as_str(), "large.txt");
     assert_md5(Some("99f7ad3d42ac3318dcc92b64beecb179"), &information);
     assert_eq!(information.path, Vec::<String>::new());
     close_thumbnail(guard, &information).await?;
 
     let location = FileLocation { storage: root.storage, file_id: list.files[2].id, is_directory: true, };
-    let information = files_get(c!(guard), location, false, false).await?;
+    let information = api!(files_get(guard, location, false, false))?;
     assert_eq!(information.basic.name.as_str(), "hello");
     assert_md5(None, &information);
     assert_eq!(information.path, Vec::<String>::new());
@@ -85,7 +85,7 @@ pub async fn test_normal(guard: &InitializeGuard, root: FileLocation) -> anyhow:
 
     let hello = super::list::list(guard, location, None).await?;
     let location = FileLocation { storage: root.storage, file_id: hello.files[0].id, is_directory: false, };
-    let information = files_get(c!(guard), location, false, false).await?;
+    let information = api!(files_get(guard, location, false, false))?;
     assert_eq!(information.basic.name.as_str(), "hello.txt");
     assert_md5(Some("fc3ff98e8c6a0d3087d515c0473f8677"), &information);
     assert_eq!(information.path, vec!["hello".to_string()]);
@@ -95,7 +95,7 @@ pub async fn test_normal(guard: &InitializeGuard, root: FileLocation) -> anyhow:
 }
 
 pub async fn test_empty(guard: &InitializeGuard, root: FileLocation) -> anyhow::Result<()> {
-    let information = files_get(c!(guard), root, false, false).await?;
+    let information = api!(files_get(guard, root, false, false))?;
     assert_eq!(information.basic.id, information.basic.parent_id);
     close_thumbnail(guard, &information).await?;
     Ok(())
@@ -0,0 +1,120 @@
+use std::fmt::Debug;
+
+use bytes::{BufMut, BytesMut};
+use rand::Rng;
+use tokio::sync::watch::channel;
+use tracing::debug;
+
+use wlist_native::common::data::files::options::Duplicate;
+use wlist_native::common::data::files::FileLocation;
+use wlist_native::core::client::download::download_request;
+use wlist_native::core::client::trash::{trash_delete, trash_trash};
+use wlist_native::core::client::upload::{upload_cancel, upload_confirm, upload_extra_md5s, upload_finish, upload_request, upload_stream};
+
+use crate::core::{api, InitializeGuard};
+
+#[derive(Debug, Clone, Copy)]
+enum Case {
+    /// The first chunk has the declared length but different content.
+    Corrupted,
+    /// The first chunk misses its last byte.
+    Short,
+    /// The first chunk carries one extra byte.
+    Long,
+    /// The first chunk is streamed to an id after the last chunk.
+    OutOfRange,
+    /// The first chunk is streamed again with different content.
+    Twice,
+}
+
+/// Returns the name of the typed exception, or the error itself if it is untyped.
+fn rejected<T: Debug>(result: anyhow::Result<T>) -> anyhow::Result<Option<&'static str>> {
+    let error = match result {
+        Ok(_) => return Ok(None),
+        Err(error) => error,
+    };
+    if error.downcast_ref::<wlist_native::common::exceptions::IncorrectArgumentError>().is_some() {
+        return Ok(Some("IncorrectArgumentError"));
+    }
+    if error.downcast_ref::<wlist_native::common::exceptions::TokenExpiredError>().is_some() {
+        return Ok(Some("TokenExpiredError"));
+    }
+    Err(error)
+}
+
+async fn test_case(guard: &InitializeGuard, root: FileLocation, case: Case) -> anyhow::Result<()> {
+    let mut data = BytesMut::zeroed(rand::thread_rng().gen_range(128..4<<10));
+    rand::thread_rng().fill(&mut data[..]);
+    let data = data.freeze();
+    let chunk = api!(upload_extra_md5s(guard, root.storage))?.map(|chunk| chunk.get());
+    let (md5, md5s) = super::upload::hash(&data, chunk).await;
+    let name = format!("Integrity{case:?}.txt");
+
+    let confirmation = api!(upload_request(guard, root, name.clone(), data.len() as u64, md5, md5s, Duplicate::Error))?;
+    assert_eq!(confirmation.done, false); // Random data never hits.
+    let token = confirmation.token;
+    let information = api!(upload_confirm(guard, token.clone()))?;
+    let count = information.chunks.len();
+
+    // Whether the server may legitimately ignore the bad bytes and still finish with the original data.
+    let mut ignorable = false;
+    for (chunk, id) in information.chunks.iter().zip(0..) {
+        let slice = data.slice(chunk.start as usize..(chunk.start + chunk.size) as usize);
+        if id != 0 {
+            api!(upload_stream(guard, token.clone(), id, &mut slice.clone(), channel(0).0, channel(true).1))?;
+            continue;
+        }
+        let mut corrupted = BytesMut::from(&slice[..]);
+        if let Some(b) = corrupted.first_mut() { *b = !*b; }
+        let corrupted = corrupted.freeze();
+        let result = match case {
+            Case::Corrupted => api!(upload_stream(guard, token.clone(), id, &mut corrupted.clone(), channel(0).0, channel(true).1)),
+            Case::Short => api!(upload_stream(guard, token.clone(), id, &mut slice.slice(..slice.len().saturating_sub(1)), channel(0).0, channel(true).1)),
+            Case::Long => {
+                let mut long = BytesMut::from(&slice[..]);
+                long.put_u8(0);
+                let mut long = long.freeze();
+                let result = api!(upload_stream(guard, token.clone(), id, &mut long, channel(0).0, channel(true).1));
+                ignorable = result.is_ok() && long.len() == 1;
+                result
+            },
+            Case::OutOfRange => api!(upload_stream(guard, token.clone(), count as _, &mut slice.clone(), channel(0).0, channel(true).1)),
+            Case::Twice => {
+                api!(upload_stream(guard, token.clone(), id, &mut slice.clone(), channel(0).0, channel(true).1))?;
+                let result = api!(upload_stream(guard, token.clone(), id, &mut corrupted.clone(), channel(0).0, channel(true).1));
+                ignorable = result.is_err();
+                result
+            },
+        };
+        let error = rejected(result)?;
+        debug!(?case, ?error, "Streamed the bad chunk.");
+    }
+
+    let result = api!(upload_finish(guard, token.clone()));
+    let result = match (ignorable, result) {
+        (true, Ok(information)) => {
+            // The surplus was dropped, so the file must be exactly what was declared.
+            let confirmation = api!(download_request(guard, information.get_location(root.storage), 0, u64::MAX))?;
+            let (downloaded, _, _) = super::download::download0(guard, &confirmation.token).await?;
+            assert_eq!(data, downloaded, "{case:?}: data != downloaded");
+            let information = api!(trash_trash(guard, information.get_location(root.storage)))?;
+            return api!(trash_delete(guard, information.get_location(root.storage)));
+        },
+        (_, result) => result,
+    };
+    let error = rejected(result)?;
+    assert!(error.is_some(), "{case:?}: upload_finish succeeded");
+    let result = api!(upload_cancel(guard, token));
+    crate::may_error::<_, wlist_native::common::exceptions::TokenExpiredError>(result)?;
+
+    let list = super::list::list(guard, root, None).await?;
+    assert!(list.files.iter().all(|i| i.name.as_str() != name), "{case:?}: half-written file is visible: {list:?}");
+    Ok(())
+}
+
+pub async fn test_normal(guard: &InitializeGuard, root: FileLocation) -> anyhow::Result<()> {
+    for case in [Case::Corrupted, Case::Short, Case::Long, Case::OutOfRange, Case::Twice] {
+        test_case(guard, root, case).await?;
+    }
+    Ok(())
+}
@@ -0,0 +1,180 @@
+use std::cmp::min;
+use std::io::SeekFrom;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use bytes::{BufMut, Bytes, BytesMut};
+use tokio::fs::{File, OpenOptions};
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
+use tokio::sync::watch::channel;
+use tracing::info;
+
+use wlist_native::common::data::files::information::FileInformation;
+use wlist_native::common::data::files::options::Duplicate;
+use wlist_native::common::data::files::tokens::DownloadToken;
+use wlist_native::common::data::files::FileLocation;
+use wlist_native::core::client::download::{download_confirm, download_finish, download_request, download_stream};
+use wlist_native::core::client::storages::storages_get;
+use wlist_native::core::client::trash::{trash_delete, trash_trash};
+use wlist_native::core::client::upload::{upload_confirm, upload_extra_md5s, upload_finish, upload_request, upload_stream};
+use wlist_native::core::helper::hasher::Md5Hasher;
+
+use crate::core::{api, InitializeGuard};
+
+/// Bytes to transfer, overridable by `WLIST_TEST_LARGE_SIZE`.
+const SIZE: u64 = 2 << 30;
+/// The only buffer held at once during a transfer.
+const BUFFER: usize = 1 << 20;
+/// Allowed growth of the resident set while transferring [SIZE] bytes.
+const MEMORY_LIMIT: u64 = 256 << 20;
+
+/// Reads up to `len` bytes from the current position of `file`, stopping early only at EOF.
+async fn read(file: &mut File, len: usize) -> anyhow::Result<Bytes> {
+    let mut buffer = vec![0; len];
+    let mut read = 0;
+    while read < len {
+        let n = file.read(&mut buffer[read..]).await?;
+        if n == 0 { break; }
+        read += n;
+    }
+    buffer.truncate(read);
+    Ok(Bytes::from(buffer))
+}
+
+pub async fn hash_file(path: &Path, chunk: Option<usize>) -> anyhow::Result<(String, Option<Vec<String>>)> {
+    let mut file = File::open(path).await?;
+    let md5 = Md5Hasher::new();
+    let mut md5s = Vec::new();
+    let mut part = Md5Hasher::new();
+    let mut part_len = 0;
+    loop {
+        let mut buffer = read(&mut file, BUFFER).await?;
+        if buffer.is_empty() { break; }
+        md5.update(buffer.clone()).await;
+        let Some(chunk) = chunk else { continue };
+        while !buffer.is_empty() {
+            let piece = buffer.split_to(min(buffer.len(), chunk - part_len));
+            part_len += piece.len();
+            part.update(piece).await;
+            if part_len == chunk {
+                md5s.push(std::mem::replace(&mut part, Md5Hasher::new()).finalize().await);
+                part_len = 0;
+            }
+        }
+    }
+    let md5 = md5.finalize().await;
+    if part_len > 0 { md5s.push(part.finalize().await); }
+    if md5s.is_empty() { md5s.push(md5.clone()); }
+    Ok((md5, chunk.map(|_| md5s)))
+}
+
+/// Like [super::upload::upload], but streams `path` from disk through a bounded buffer.
+pub async fn upload_file(guard: &InitializeGuard, parent: FileLocation, name: String, path: &Path, duplicate: Duplicate) -> anyhow::Result<FileInformation> {
+    let len = tokio::fs::metadata(path).await?.len();
+    let chunk = api!(upload_extra_md5s(guard, parent.storage))?.map(|chunk| chunk.get());
+    let (md5, md5s) = hash_file(path, chunk).await?;
+    let confirmation = api!(upload_request(guard, parent, name, len, md5, md5s, duplicate))?;
+    if !confirmation.done {
+        let information = api!(upload_confirm(guard, confirmation.token.clone()))?;
+        let mut file = File::open(path).await?;
+        for (chunk, id) in information.chunks.into_iter().zip(0..) {
+            file.seek(SeekFrom::Start(chunk.start)).await?;
+            let mut remaining = chunk.size as usize;
+            while remaining > 0 {
+                let mut buffer = read(&mut file, min(BUFFER, remaining)).await?;
+                anyhow::ensure!(!buffer.is_empty(), "{} is shorter than {len} bytes", path.display());
+                remaining -= buffer.len();
+                api!(upload_stream(guard, confirmation.token.clone(), id, &mut buffer, channel(0).0, channel(true).1))?;
+            }
+        }
+    }
+    let information = api!(upload_finish(guard, confirmation.token))?;
+    assert_eq!(information.size, Some(len));
+    Ok(information)
+}
+
+/// Like [super::download::download0], but writes into `path` through a bounded buffer.
+pub async fn download_file(guard: &InitializeGuard, token: &DownloadToken, path: &Path) -> anyhow::Result<(u64, u64)> {
+    let information = api!(download_confirm(guard, token.clone()))?;
+    let mut file = OpenOptions::new().create(true).write(true).truncate(true).open(path).await?;
+    let Some(first) = information.chunks.first() else { return Ok((0, 0)) };
+    let l = first.start;
+    let mut r = l;
+    for (chunk, id) in information.chunks.iter().zip(0..) {
+        assert_eq!(r, chunk.start);
+        file.seek(SeekFrom::Start(chunk.start - l)).await?;
+        let mut done = 0;
+        while done < chunk.size {
+            let size = min(BUFFER as u64, chunk.size - done) as usize;
+            let mut buffer = BytesMut::new().limit(size);
+            // Ranged chunks are fetched from an offset, the others continue from where the last call stopped.
+            let start = if chunk.range { done } else { 0 };
+            api!(download_stream(guard, token.clone(), id, start, &mut buffer, channel(0).0, channel(true).1))?;
+            let buffer = buffer.into_inner();
+            file.write_all(&buffer).await?;
+            done += buffer.len() as u64;
+            if buffer.len() < size { break; }
+        }
+        r += done;
+    }
+    api!(download_finish(guard, token.clone()))?;
+    file.flush().await?;
+    Ok((l, r))
+}
+
+/// Returns the resident set size of this process, if the platform reports it.
+/// `VmRSS` is given in kB, so it does not depend on the page size as `statm` does.
+fn resident() -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find_map(|line| line.strip_prefix("VmRSS:"))?;
+    let kilobytes = line.trim().strip_suffix("kB")?.trim().parse::<u64>().ok()?;
+    Some(kilobytes << 10)
+}
+
+pub async fn test_normal(guard: &InitializeGuard, root: FileLocation) -> anyhow::Result<()> {
+    let max = api!(storages_get(guard, root.storage, false))?.max_size_per_file;
+    let size = std::env::var("WLIST_TEST_LARGE_SIZE").ok().and_then(|s| s.parse().ok()).unwrap_or(SIZE);
+    let size = min(size, max);
+    let directory = std::env::temp_dir();
+    let source = directory.join(format!("wlist-large-{}-source.bin", std::process::id()));
+    let target = directory.join(format!("wlist-large-{}-target.bin", std::process::id()));
+    File::create(&source).await?.set_len(size).await?; // sparse
+
+    let peak = Arc::new(AtomicU64::new(0));
+    let baseline = resident();
+    let sampler = tokio::spawn({
+        let peak = Arc::clone(&peak);
+        async move { loop {
+            if let Some(resident) = resident() { peak.fetch_max(resident, Ordering::Relaxed); }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        } }
+    });
+
+    let result = transfer(guard, root, &source, &target, size).await;
+    sampler.abort();
+    let _ = tokio::fs::remove_file(&source).await;
+    let _ = tokio::fs::remove_file(&target).await;
+    result?;
+
+    if let Some(baseline) = baseline {
+        let peak = peak.load(Ordering::Relaxed);
+        info!(%size, %baseline, %peak, "Large file transferred.");
+        assert!(peak.saturating_sub(baseline) < MEMORY_LIMIT, "resident memory grew from {baseline} to {peak} bytes");
+    }
+    Ok(())
+}
+
+async fn transfer(guard: &InitializeGuard, root: FileLocation, source: &Path, target: &Path, size: u64) -> anyhow::Result<()> {
+    let file = upload_file(guard, root, "UploadHuge.bin".to_string(), source, Duplicate::Error).await?;
+    let confirmation = api!(download_request(guard, file.get_location(root.storage), 0, u64::MAX))?;
+    assert_eq!(confirmation.size, size);
+    let (l, r) = download_file(guard, &confirmation.token, target).await?;
+    assert_eq!(l, 0); assert_eq!(r, size);
+    let (expected, _) = hash_file(source, None).await?;
+    let (actual, _) = hash_file(target, None).await?;
+    assert_eq!(expected, actual);
+    let information = api!(trash_trash(guard, file.get_location(root.storage)))?;
+    api!(trash_delete(guard, information.get_location(root.storage)))
+}
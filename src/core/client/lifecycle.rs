@@ -0,0 +1,185 @@
+use bytes::{BufMut, Bytes, BytesMut};
+use rand::Rng;
+use tokio::sync::watch::channel;
+
+use wlist_native::common::data::files::information::FileInformation;
+use wlist_native::common::data::files::options::Duplicate;
+use wlist_native::common::data::files::FileLocation;
+use wlist_native::common::data::files::tokens::{DownloadToken, UploadToken};
+use wlist_native::core::client::download::{download_cancel, download_confirm, download_finish, download_request, download_stream};
+use wlist_native::core::client::trash::{trash_delete, trash_trash};
+use wlist_native::core::client::upload::{upload_cancel, upload_confirm, upload_extra_md5s, upload_finish, upload_request, upload_stream};
+
+use crate::core::{api, InitializeGuard};
+
+/// Chunk id that is never handed out for the small fixture files.
+const UNKNOWN_CHUNK: u32 = 1000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DownloadState {
+    Requested,
+    Confirmed,
+    /// Finished or cancelled.
+    Gone,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum UploadState {
+    Requested,
+    Confirmed,
+    /// Every chunk is uploaded.
+    Streamed,
+    /// Finished or cancelled.
+    Gone,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Call {
+    Confirm,
+    Stream,
+    StreamUnknown,
+    Finish,
+    Cancel,
+}
+
+static CALLS: &[Call] = &[Call::Confirm, Call::Stream, Call::StreamUnknown, Call::Finish, Call::Cancel];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Outcome {
+    Ok,
+    TokenExpired,
+    IncorrectArgument,
+}
+
+fn classify<T>(result: anyhow::Result<T>) -> anyhow::Result<Outcome> {
+    match result {
+        Ok(_) => Ok(Outcome::Ok),
+        Err(e) if e.downcast_ref::<wlist_native::common::exceptions::TokenExpiredError>().is_some() => Ok(Outcome::TokenExpired),
+        Err(e) if e.downcast_ref::<wlist_native::common::exceptions::IncorrectArgumentError>().is_some() => Ok(Outcome::IncorrectArgument),
+        Err(e) => Err(e),
+    }
+}
+
+/// Every sequence of `len` calls, in lexicographic order.
+fn sequences(len: u32) -> impl Iterator<Item=Vec<Call>> {
+    (0..CALLS.len().pow(len)).map(move |mut i| (0..len).map(|_| {
+        let call = CALLS[i % CALLS.len()];
+        i /= CALLS.len();
+        call
+    }).collect())
+}
+
+/// The protocol of a download token.
+/// Only a confirmed token can be streamed, finishing does not require all chunks,
+/// and any call after finish or cancel sees an expired token.
+fn download_transition(state: DownloadState, call: Call) -> (Outcome, DownloadState) {
+    use DownloadState::*;
+    match (state, call) {
+        (Requested, Call::Confirm) => (Outcome::Ok, Confirmed),
+        (Requested, Call::Cancel) => (Outcome::Ok, Gone),
+        (Requested, _) => (Outcome::TokenExpired, Requested),
+        (Confirmed, Call::Confirm) => (Outcome::TokenExpired, Confirmed),
+        (Confirmed, Call::Stream) => (Outcome::Ok, Confirmed),
+        (Confirmed, Call::StreamUnknown) => (Outcome::IncorrectArgument, Confirmed),
+        (Confirmed, Call::Finish | Call::Cancel) => (Outcome::Ok, Gone),
+        (Gone, _) => (Outcome::TokenExpired, Gone),
+    }
+}
+
+async fn download_call(guard: &InitializeGuard, token: &DownloadToken, call: Call) -> anyhow::Result<Outcome> {
+    let stream = |id| async move {
+        api!(download_stream(guard, token.clone(), id, 0, &mut BytesMut::new().limit(32), channel(0).0, channel(true).1))
+    };
+    classify(match call {
+        Call::Confirm => api!(download_confirm(guard, token.clone())).map(drop),
+        Call::Stream => stream(0).await.map(drop),
+        Call::StreamUnknown => stream(UNKNOWN_CHUNK as _).await.map(drop),
+        Call::Finish => api!(download_finish(guard, token.clone())).map(drop),
+        Call::Cancel => api!(download_cancel(guard, token.clone())).map(drop),
+    })
+}
+
+pub async fn test_download(guard: &InitializeGuard, root: FileLocation) -> anyhow::Result<()> {
+    let list = super::list::list(guard, root, None).await?;
+    let chunk = FileLocation { storage: root.storage, file_id: list.files[0].id, is_directory: false, };
+
+    for sequence in sequences(3) {
+        let confirmation = api!(download_request(guard, chunk, 0, u64::MAX))?;
+        let mut state = DownloadState::Requested;
+        for (i, call) in sequence.iter().enumerate() {
+            let (expected, next) = download_transition(state, *call);
+            let outcome = download_call(guard, &confirmation.token, *call).await?;
+            assert_eq!(outcome, expected, "download token: {call:?} in {state:?} (step {i} of {sequence:?})");
+            state = next;
+        }
+        if state != DownloadState::Gone {
+            api!(download_cancel(guard, confirmation.token))?;
+        }
+    }
+    Ok(())
+}
+
+/// The protocol of an upload token for a file with a single chunk.
+/// Finishing requires every chunk to be streamed exactly once,
+/// and any call after finish or cancel sees an expired token.
+fn upload_transition(state: UploadState, call: Call) -> (Outcome, UploadState) {
+    use UploadState::*;
+    match (state, call) {
+        (Requested, Call::Confirm) => (Outcome::Ok, Confirmed),
+        (Requested, Call::Cancel) => (Outcome::Ok, Gone),
+        (Requested, _) => (Outcome::TokenExpired, Requested),
+        (Confirmed | Streamed, Call::Confirm) => (Outcome::TokenExpired, state),
+        (Confirmed, Call::Stream) => (Outcome::Ok, Streamed),
+        (Confirmed, Call::Finish) => (Outcome::IncorrectArgument, Confirmed),
+        (Streamed, Call::Stream) => (Outcome::IncorrectArgument, Streamed),
+        (Streamed, Call::Finish) => (Outcome::Ok, Gone),
+        (Confirmed | Streamed, Call::StreamUnknown) => (Outcome::IncorrectArgument, state),
+        (Confirmed | Streamed, Call::Cancel) => (Outcome::Ok, Gone),
+        (Gone, _) => (Outcome::TokenExpired, Gone),
+    }
+}
+
+async fn upload_call(guard: &InitializeGuard, token: &UploadToken, data: &Bytes, call: Call) -> anyhow::Result<(Outcome, Option<FileInformation>)> {
+    let stream = |id| async move {
+        api!(upload_stream(guard, token.clone(), id, &mut data.clone(), channel(0).0, channel(true).1))
+    };
+    let mut finished = None;
+    let outcome = classify(match call {
+        Call::Confirm => api!(upload_confirm(guard, token.clone())).map(drop),
+        Call::Stream => stream(0).await.map(drop),
+        Call::StreamUnknown => stream(UNKNOWN_CHUNK as _).await.map(drop),
+        Call::Finish => api!(upload_finish(guard, token.clone())).map(|information| finished = Some(information)),
+        Call::Cancel => api!(upload_cancel(guard, token.clone())).map(drop),
+    })?;
+    Ok((outcome, finished))
+}
+
+pub async fn test_upload(guard: &InitializeGuard, root: FileLocation) -> anyhow::Result<()> {
+    let chunk = api!(upload_extra_md5s(guard, root.storage))?.map(|chunk| chunk.get());
+    for (n, sequence) in sequences(3).enumerate() {
+        // Fresh data each time, so finished sequences never make later requests hit.
+        let mut data = BytesMut::zeroed(16);
+        rand::thread_rng().fill(&mut data[..]);
+        let data = data.freeze();
+        let (md5, md5s) = super::upload::hash(&data, chunk).await;
+        let confirmation = api!(upload_request(guard, root, format!("Lifecycle-{n}.txt"), data.len() as u64, md5, md5s, Duplicate::Error))?;
+        assert_eq!(confirmation.done, false); // Random data never hits.
+        let mut state = UploadState::Requested;
+        let mut file = None;
+        for (i, call) in sequence.iter().enumerate() {
+            let (expected, next) = upload_transition(state, *call);
+            let (outcome, finished) = upload_call(guard, &confirmation.token, &data, *call).await?;
+            assert_eq!(outcome, expected, "upload token: {call:?} in {state:?} (step {i} of {sequence:?})");
+            file = file.or(finished);
+            state = next;
+        }
+        if state != UploadState::Gone {
+            api!(upload_cancel(guard, confirmation.token))?;
+        }
+        if let Some(file) = file {
+            let information = api!(trash_trash(guard, file.get_location(root.storage)))?;
+            api!(trash_delete(guard, information.get_location(root.storage)))?;
+        }
+    }
+    Ok(())
+}
@@ -6,7 +6,7 @@ use wlist_native::common::data::files::FileLocation;
 use wlist_native::common::data::Direction;
 use wlist_native::core::client::files::{files_copy, files_list, files_move, files_rename};
 
-use crate::core::{c, InitializeGuard};
+use crate::core::{api, InitializeGuard};
 
 pub async fn test_none(guard: &InitializeGuard) -> anyhow::Result<()> {
     let file = FileLocation { storage: 0, file_id: 0, is_directory: false, };
@@ -15,24 +15,24 @@ pub async fn test_none(guard: &InitializeGuard) -> anyhow::Result<()> {
         filter: FilesFilter::Both, orders: Default::default(), offset: 0, limit: 0,
     };
 
-    let result = files_list(c!(guard), file, options.clone()).await;
+    let result = api!(files_list(guard, file, options.clone()));
     crate::assert_error::<_, wlist_native::common::exceptions::IncorrectArgumentError>(result)?;
-    let result = files_list(c!(guard), directory, options).await;
+    let result = api!(files_list(guard, directory, options));
     crate::assert_error::<_, wlist_native::common::exceptions::StorageNotFoundError>(result)?;
 
-    let result = files_copy(c!(guard), file, directory, "none".to_string(), Duplicate::Error).await;
+    let result = api!(files_copy(guard, file, directory, "none".to_string(), Duplicate::Error));
     crate::assert_error::<_, wlist_native::common::exceptions::StorageNotFoundError>(result)?;
-    let result = files_copy(c!(guard), directory, directory, "none".to_string(), Duplicate::Error).await;
+    let result = api!(files_copy(guard, directory, directory, "none".to_string(), Duplicate::Error));
     crate::assert_error::<_, wlist_native::common::exceptions::StorageNotFoundError>(result)?;
 
-    let result = files_move(c!(guard), file, directory, Duplicate::Error).await;
+    let result = api!(files_move(guard, file, directory, Duplicate::Error));
     crate::assert_error::<_, wlist_native::common::exceptions::StorageNotFoundError>(result)?;
-    let result = files_move(c!(guard), directory, directory, Duplicate::Error).await;
+    let result = api!(files_move(guard, directory, directory, Duplicate::Error));
     crate::assert_error::<_, wlist_native::common::exceptions::StorageNotFoundError>(result)?;
 
-    let result = files_rename(c!(guard), file, "none".to_string(), Duplicate::Error).await;
+    let result = api!(files_rename(guard, file, "none".to_string(), Duplicate::Error));
     crate::assert_error::<_, wlist_native::common::exceptions::StorageNotFoundError>(result)?;
-    let result = files_rename(c!(guard), directory, "none".to_string(), Duplicate::Error).await;
+    let result = api!(files_rename(guard, directory, "none".to_string(), Duplicate::Error));
     crate::assert_error::<_, wlist_native::common::exceptions::StorageNotFoundError>(result)?;
 
     Ok(())
@@ -42,27 +42,27 @@ pub async fn list(guard: &InitializeGuard, directory: FileLocation, options: Opt
     let options = options.unwrap_or(ListFileOptions {
         filter: FilesFilter::Both, orders: IndexMap::from([(FilesOrder::Name, Direction::ASCEND)]), offset: 0, limit: 10,
     });
-    let confirmation = match files_list(c!(guard), directory, options.clone()).await? {
+    let confirmation = match api!(files_list(guard, directory, options.clone()))? {
         either::Either::Left(list) => return Ok(list),
         either::Either::Right(c) => c,
     };
     super::refresh::refresh(guard, confirmation.token).await?;
-    Ok(files_list(c!(guard), directory, options).await?.unwrap_left())
+    Ok(api!(files_list(guard, directory, options))?.unwrap_left())
 }
 
 pub async fn test_normal(guard: &InitializeGuard, root: FileLocation) -> anyhow::Result<()> {
     // normal_test
-    let list = files_list(c!(guard), root, ListFileOptions {
+    let list = api!(files_list(guard, root, ListFileOptions {
         filter: FilesFilter::Both, orders: Default::default(), offset: 0, limit: 7,
-    }).await?.unwrap_left(); // this is tested after refresh, so needn't refresh.
+    }))?.unwrap_left(); // this is tested after refresh, so needn't refresh.
     assert_eq!(list.total_file, 2);
     assert_eq!(list.total_directory, 4);
     assert_eq!(list.files.len(), 6);
 
     // normal_test_limit
-    let list = files_list(c!(guard), root, ListFileOptions {
+    let list = api!(files_list(guard, root, ListFileOptions {
         filter: FilesFilter::Both, orders: IndexMap::from([(FilesOrder::Name, Direction::ASCEND)]), offset: 0, limit: 4,
-    }).await?.unwrap_left();
+    }))?.unwrap_left();
     assert_eq!(list.total_file, 2);
     assert_eq!(list.total_directory, 4);
     assert_eq!(list.files.len(), 4);
@@ -72,9 +72,9 @@ pub async fn test_normal(guard: &InitializeGuard, root: FileLocation) -> anyhow:
     assert_eq!(list.files[3].name.as_str(), "large.txt");
 
     // normal_test_offset
-    let list = files_list(c!(guard), root, ListFileOptions {
+    let list = api!(files_list(guard, root, ListFileOptions {
         filter: FilesFilter::Both, orders: IndexMap::from([(FilesOrder::Name, Direction::ASCEND)]), offset: 4, limit: 3,
-    }).await?.unwrap_left();
+    }))?.unwrap_left();
     assert_eq!(list.total_file, 2);
     assert_eq!(list.total_directory, 4);
     assert_eq!(list.files.len(), 2);
@@ -83,34 +83,34 @@ pub async fn test_normal(guard: &InitializeGuard, root: FileLocation) -> anyhow:
 
 
     // filter_test_directory
-    let list = files_list(c!(guard), root, ListFileOptions {
+    let list = api!(files_list(guard, root, ListFileOptions {
         filter: FilesFilter::OnlyDirectories, orders: Default::default(), offset: 0, limit: 5,
-    }).await?.unwrap_left();
+    }))?.unwrap_left();
     assert_eq!(list.total_file, 2);
     assert_eq!(list.total_directory, 4);
     assert_eq!(list.files.len(), 4);
 
     // filter_test_file
-    let list = files_list(c!(guard), root, ListFileOptions {
+    let list = api!(files_list(guard, root, ListFileOptions {
         filter: FilesFilter::OnlyFiles, orders: Default::default(), offset: 0, limit: 3,
-    }).await?.unwrap_left();
+    }))?.unwrap_left();
     assert_eq!(list.total_file, 2);
     assert_eq!(list.total_directory, 4);
     assert_eq!(list.files.len(), 2);
 
     // filter_test_count
-    let list = files_list(c!(guard), root, ListFileOptions {
+    let list = api!(files_list(guard, root, ListFileOptions {
         filter: FilesFilter::OnlyFiles, orders: Default::default(), offset: 0, limit: 0,
-    }).await?.unwrap_left();
+    }))?.unwrap_left();
     assert_eq!(list.total_file, 2);
     assert_eq!(list.total_directory, 4);
     assert_eq!(list.files.len(), 0);
 
 
     // order_test_name
-    let list = files_list(c!(guard), root, ListFileOptions {
+    let list = api!(files_list(guard, root, ListFileOptions {
         filter: FilesFilter::Both, orders: IndexMap::from([(FilesOrder::Name, Direction::ASCEND)]), offset: 0, limit: 7,
-    }).await?.unwrap_left();
+    }))?.unwrap_left();
     assert_eq!(list.total_file, 2);
     assert_eq!(list.total_directory, 4);
     assert_eq!(list.files.len(), 6);
@@ -122,9 +122,9 @@ pub async fn test_normal(guard: &InitializeGuard, root: FileLocation) -> anyhow:
     assert_eq!(list.files[5].name.as_str(), "special");
 
     // order_test_suffix
-    let list = files_list(c!(guard), root, ListFileOptions {
+    let list = api!(files_list(guard, root, ListFileOptions {
         filter: FilesFilter::Both, orders: IndexMap::from([(FilesOrder::Suffix, Direction::ASCEND), (FilesOrder::Name, Direction::DESCEND)]), offset: 0, limit: 7,
-    }).await?.unwrap_left();
+    }))?.unwrap_left();
     assert_eq!(list.total_file, 2);
     assert_eq!(list.total_directory, 4);
     assert_eq!(list.files.len(), 6);
@@ -136,9 +136,9 @@ pub async fn test_normal(guard: &InitializeGuard, root: FileLocation) -> anyhow:
     assert_eq!(list.files[5].name.as_str(), "chunk.txt");
 
     // order_test_directory
-    let list = files_list(c!(guard), root, ListFileOptions {
+    let list = api!(files_list(guard, root, ListFileOptions {
         filter: FilesFilter::Both, orders: IndexMap::from([(FilesOrder::Directory, Direction::ASCEND), (FilesOrder::Name, Direction::ASCEND)]), offset: 0, limit: 7,
-    }).await?.unwrap_left();
+    }))?.unwrap_left();
     assert_eq!(list.total_file, 2);
     assert_eq!(list.total_directory, 4);
     assert_eq!(list.files.len(), 6);
@@ -182,9 +182,9 @@ pub async fn test_normal(guard: &InitializeGuard, root: FileLocation) -> anyhow:
 
 pub async fn test_empty(guard: &InitializeGuard, root: FileLocation) -> anyhow::Result<()> {
     // normal_test
-    let list = files_list(c!(guard), root, ListFileOptions {
+    let list = api!(files_list(guard, root, ListFileOptions {
         filter: FilesFilter::Both, orders: Default::default(), offset: 0, limit: 1,
-    }).await?.unwrap_left(); // this is tested after refresh, so needn't refresh.
+    }))?.unwrap_left(); // this is tested after refresh, so needn't refresh.
     assert_eq!(list.total_file, 0);
     assert_eq!(list.total_directory, 0);
     assert_eq!(list.files.len(), 0);
@@ -1,3 +1,6 @@
+//! Ids across renames and moves: a file keeps its id, as `rename` and `move` assert, while a directory may get a new one.
+//! `race`, `stress` and `check_name` follow entries by this rule.
+
 use wlist_native::common::data::files::FileLocation;
 use wlist_native::common::data::storages::StorageType;
 
@@ -12,7 +15,26 @@ mod trash;
 mod copy;
 mod r#move;
 mod rename;
+mod stress;
+mod race;
+mod roundtrip;
+mod resume;
+mod integrity;
+mod large;
+mod bench;
+mod lifecycle;
+mod expiry;
+mod thumbnail;
+mod fixtures;
+
+/// Fails a single module of a suite that runs longer than `WLIST_TEST_PHASE_TIMEOUT` seconds.
+macro_rules! phase {
+    ($f: expr) => {
+        crate::timeout(stringify!($f), crate::timeout_from_env("WLIST_TEST_PHASE_TIMEOUT", std::time::Duration::from_secs(30 * 60)), $f).await
+    };
+}
 
+/// Not recorded by `api!`, since the account configs carry credentials.
 macro_rules! add_storage {
     ($f: ident($g: ident, $n: expr, $c: literal)) => {
         wlist_native::core::client::storages::$f(
@@ -82,20 +104,33 @@ async fn test_normal(guard: &super::InitializeGuard, storage: StorageType) -> an
 
     let info = storages::test_single(guard, &info).await?;
     let root = FileLocation { storage: info.id, file_id: info.root_directory_id, is_directory: true };
-    refresh::test_normal(guard, root).await?;
-    list::test_normal(guard, root).await?;
-    get::test_normal(guard, root).await?;
-    download::test_normal(guard, root).await?;
-    check_name::test_normal(guard, root).await?;
-    upload::test_normal(guard, root).await?;
-    trash::test_normal(guard, root).await?;
-    copy::test_normal(guard, root).await?;
-    r#move::test_normal(guard, root).await?;
-    rename::test_normal(guard, root).await?;
+    phase!(refresh::test_normal(guard, root))?;
+    phase!(list::test_normal(guard, root))?;
+    phase!(get::test_normal(guard, root))?;
+    phase!(download::test_normal(guard, root))?;
+    phase!(check_name::test_normal(guard, root))?;
+    phase!(upload::test_normal(guard, root))?;
+    phase!(trash::test_normal(guard, root))?;
+    phase!(copy::test_normal(guard, root))?;
+    phase!(r#move::test_normal(guard, root))?;
+    phase!(rename::test_normal(guard, root))?;
+    if storage == StorageType::Mocker {
+        phase!(download::test_concurrent(guard, root))?;
+        phase!(thumbnail::test_normal(guard, root))?;
+        phase!(fixtures::test_normal(guard, root))?;
+        phase!(lifecycle::test_download(guard, root))?;
+        phase!(lifecycle::test_upload(guard, root))?;
+        phase!(upload::test_instant(guard, root))?;
+        phase!(stress::test_normal(guard, root))?;
+        phase!(race::test_normal(guard, root))?;
+        phase!(roundtrip::test_normal(guard, root))?;
+        phase!(resume::test_normal(guard, root))?;
+        phase!(integrity::test_normal(guard, root))?;
+    }
 
     {
         // extra test for files_get on root
-        let info = wlist_native::core::client::storages::storages_get(super::c!(guard), info.id, false).await?;
+        let info = super::api!(wlist_native::core::client::storages::storages_get(guard, info.id, false))?;
         if info.size.is_some() { // fully indexed.
             assert!(
                 info.indexed_size == (4 << 10) + (12 << 20) + 12 + 14 ||
@@ -103,7 +138,7 @@ async fn test_normal(guard: &super::InitializeGuard, storage: StorageType) -> an
                 "{}", info.indexed_size
             );
             assert_eq!(info.size, Some(info.indexed_size));
-            let root = wlist_native::core::client::files::files_get(super::c!(guard), root, true, false).await?;
+            let root = super::api!(wlist_native::core::client::files::files_get(guard, root, true, false))?;
             assert_eq!(info.as_file_details().basic, root.basic);
             assert_eq!(root.basic.size, info.size);
         }
@@ -115,24 +150,24 @@ async fn test_normal(guard: &super::InitializeGuard, storage: StorageType) -> an
         StorageType::Pan123 => add_storage!(storages_pan123_update(guard, info.id, "accounts/pan123_empty.toml"))?,
 
     };
-    let info = wlist_native::core::client::storages::storages_get(super::c!(guard), info.id, false).await?.basic;
+    let info = super::api!(wlist_native::core::client::storages::storages_get(guard, info.id, false))?.basic;
     let root = FileLocation { storage: info.id, file_id: info.root_directory_id, is_directory: true };
 
-    refresh::test_empty(guard, root).await?;
-    list::test_empty(guard, root).await?;
-    get::test_empty(guard, root).await?;
-    download::test_empty(guard, root).await?;
-    check_name::test_empty(guard, root).await?;
-    upload::test_empty(guard, root).await?;
-    trash::test_empty(guard, root).await?;
-    copy::test_empty(guard, root).await?;
-    r#move::test_empty(guard, root).await?;
-    rename::test_empty(guard, root).await?;
+    phase!(refresh::test_empty(guard, root))?;
+    phase!(list::test_empty(guard, root))?;
+    phase!(get::test_empty(guard, root))?;
+    phase!(download::test_empty(guard, root))?;
+    phase!(check_name::test_empty(guard, root))?;
+    phase!(upload::test_empty(guard, root))?;
+    phase!(trash::test_empty(guard, root))?;
+    phase!(copy::test_empty(guard, root))?;
+    phase!(r#move::test_empty(guard, root))?;
+    phase!(rename::test_empty(guard, root))?;
 
     // Ok(())
-    let result = wlist_native::core::client::storages::storages_remove(super::c!(guard), 0).await;
+    let result = super::api!(wlist_native::core::client::storages::storages_remove(guard, 0));
     crate::assert_error::<_, wlist_native::common::exceptions::StorageNotFoundError>(result)?;
-    wlist_native::core::client::storages::storages_remove(super::c!(guard), info.id).await
+    super::api!(wlist_native::core::client::storages::storages_remove(guard, info.id))
 }
 
 /// For accounts root id:
@@ -158,9 +193,88 @@ async fn test_normal(guard: &super::InitializeGuard, storage: StorageType) -> an
 async fn entry_point(storage: StorageType) -> anyhow::Result<()> {
     let guard = super::initialize(true).await?;
 
-    test_none(&guard).await?;
-    test_wrong(&guard, storage).await?;
-    test_normal(&guard, storage).await?;
+    crate::test(format!("entry_point({storage:?})"), crate::test_timeout(), async {
+        phase!(test_none(&guard))?;
+        phase!(test_wrong(&guard, storage))?;
+        test_normal(&guard, storage).await
+    }).await?;
+
+    super::uninitialize(guard).await
+}
+
+/// Throughput and latency of the mocker storage. Results are written to `run/bench/*.jsonl`.
+/// Run with `cargo test --release benchmark -- --ignored`.
+#[tokio::test]
+#[ignore]
+async fn benchmark() -> anyhow::Result<()> {
+    let guard = super::initialize(false).await?;
+
+    crate::test("benchmark".to_string(), crate::test_timeout(), async {
+        let info = add_storage!(storages_mocker_add(guard, "storage-bench", "accounts/mocker_empty.toml"))?;
+        let root = FileLocation { storage: info.id, file_id: info.root_directory_id, is_directory: true };
+        list::list(&guard, root, None).await?;
+        let result = bench::benchmark(&guard, root).await;
+        super::api!(wlist_native::core::client::storages::storages_remove(guard, info.id))?;
+        result
+    }).await?;
+
+    super::uninitialize(guard).await
+}
+
+/// Transfers a 2 GiB file and checks the resident memory stays bounded.
+/// It runs alone, so the memory of other tests does not count against it.
+/// Run with `cargo test --release large_file -- --ignored`, and set `WLIST_TEST_LARGE_SIZE` to change the size.
+#[tokio::test]
+#[ignore]
+async fn large_file() -> anyhow::Result<()> {
+    let guard = super::initialize(false).await?;
+
+    crate::test("large_file".to_string(), crate::test_timeout(), async {
+        let info = add_storage!(storages_mocker_add(guard, "storage-large", "accounts/mocker_empty.toml"))?;
+        let root = FileLocation { storage: info.id, file_id: info.root_directory_id, is_directory: true };
+        list::list(&guard, root, None).await?;
+        let result = large::test_normal(&guard, root).await;
+        super::api!(wlist_native::core::client::storages::storages_remove(guard, info.id))?;
+        result
+    }).await?;
+
+    super::uninitialize(guard).await
+}
+
+/// Idles tokens past the server's expiry window in real time, so it takes several minutes.
+/// Run with `cargo test token_expiry -- --ignored`.
+#[tokio::test]
+#[ignore]
+async fn token_expiry() -> anyhow::Result<()> {
+    let guard = super::initialize(false).await?;
+
+    crate::test("token_expiry".to_string(), crate::test_timeout(), async {
+        let info = add_storage!(storages_mocker_add(guard, "storage-expiry", "accounts/mocker.toml"))?;
+        let root = FileLocation { storage: info.id, file_id: info.root_directory_id, is_directory: true };
+        list::list(&guard, root, None).await?;
+        let result = expiry::test_normal(&guard, root).await;
+        super::api!(wlist_native::core::client::storages::storages_remove(guard, info.id))?;
+        result
+    }).await?;
+
+    super::uninitialize(guard).await
+}
+
+#[tokio::test]
+async fn events() -> anyhow::Result<()> {
+    let guard = super::initialize(true).await?;
+
+    crate::test("events".to_string(), std::time::Duration::from_secs(60), async {
+        let result = super::api!(wlist_native::core::client::storages::storages_get(guard, 0, false));
+        crate::assert_error::<_, wlist_native::common::exceptions::StorageNotFoundError>(result)
+    }).await?;
+    let events = std::fs::read_to_string(crate::events::path("events"))?;
+    let lines = events.lines().collect::<Vec<_>>();
+    assert_eq!(lines.len(), 1, "{events}");
+    assert!(lines[0].starts_with('{') && lines[0].ends_with('}'), "{events}");
+    for field in [r#""test":"events""#, r#""call":"wlist_native::core::client::storages::storages_get""#, r#""storage":0"#, r#""result":"error""#] {
+        assert!(lines[0].contains(field), "{field} in {events}");
+    }
 
     super::uninitialize(guard).await
 }
@@ -0,0 +1,223 @@
+use std::collections::BTreeSet;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Instant;
+
+use bytes::Bytes;
+use tokio::sync::Barrier;
+use tokio::task::JoinSet;
+use tracing::{debug, info};
+
+use wlist_native::common::data::files::options::Duplicate;
+use wlist_native::common::data::files::FileLocation;
+use wlist_native::core::client::files::{files_move, files_rename};
+use wlist_native::core::client::trash::{trash_delete, trash_trash};
+use wlist_native::core::client::upload::upload_mkdir;
+use wlist_native::core::client::users::users_login;
+use wlist_native::core::client::WlistClientManager;
+use wlist_native::core::server::WlistServer;
+
+use crate::core::{api, InitializeGuard};
+
+const ROUNDS: usize = 8;
+
+#[derive(Debug, Clone, Copy)]
+enum Policy {
+    Error,
+    Rename,
+    Replace,
+}
+
+impl Policy {
+    fn duplicate(self) -> Duplicate {
+        match self {
+            Policy::Error => Duplicate::Error,
+            Policy::Rename => Duplicate::Rename,
+            Policy::Replace => Duplicate::Replace,
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Call {
+    Rename { name: &'static str, policy: Policy },
+    /// `directory` is an index into [BLOCKERS].
+    Move { directory: usize, policy: Policy },
+}
+
+/// All calls are fired at once on the same file, which starts as `0/race.txt`.
+static CALLS: &[Call] = &[
+    Call::Rename { name: "x.txt", policy: Policy::Error },
+    Call::Rename { name: "y.txt", policy: Policy::Rename },
+    Call::Move { directory: 1, policy: Policy::Error },
+    Call::Move { directory: 2, policy: Policy::Replace },
+];
+
+/// Files that already exist in each directory, so the policies actually matter.
+static BLOCKERS: &[&[&str]] = &[&[], &["x.txt"], &["race.txt", "y.txt"]];
+/// The contents differ in size, which tells the raced file apart from the blockers in a listing.
+const RACE: &[u8] = b"race";
+const BLOCKER: &[u8] = b"blocker";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct State {
+    directory: usize,
+    name: String,
+    blockers: Vec<BTreeSet<String>>,
+}
+
+#[derive(Debug)]
+enum Outcome {
+    Ok { directory: usize, name: String },
+    Duplicate,
+}
+
+#[derive(Debug)]
+struct History {
+    call: usize,
+    invoked: Instant,
+    responded: Instant,
+    outcome: Outcome,
+}
+
+/// The sequential specification: returns the next state if `outcome` is a legal response of `call` in `state`.
+fn step(state: &State, call: &Call, outcome: &Outcome) -> Option<State> {
+    let (directory, name, policy) = match call {
+        Call::Rename { name, policy } => (state.directory, *name, *policy),
+        Call::Move { directory, policy } => (*directory, state.name.as_str(), *policy),
+    };
+    let conflict = state.blockers[directory].contains(name);
+    let mut next = state.clone();
+    match (outcome, conflict, policy) {
+        (Outcome::Duplicate, true, Policy::Error) => {},
+        (Outcome::Ok { directory: d, name: n }, false, _) if *d == directory && n == name => {
+            next.directory = directory;
+            next.name = n.clone();
+        },
+        (Outcome::Ok { directory: d, name: n }, true, Policy::Rename) if *d == directory && n != name && !state.blockers[directory].contains(n) => {
+            next.directory = directory;
+            next.name = n.clone();
+        },
+        (Outcome::Ok { directory: d, name: n }, true, Policy::Replace) if *d == directory && n == name => {
+            next.blockers[directory].remove(name);
+            next.directory = directory;
+            next.name = n.clone();
+        },
+        _ => return None,
+    }
+    Some(next)
+}
+
+/// Searches for a sequential order of `histories` that respects real-time order and ends in `expected`.
+fn linearize(state: &State, histories: &[History], order: &mut Vec<usize>, expected: &State) -> bool {
+    if order.len() == histories.len() {
+        return state == expected;
+    }
+    for (i, history) in histories.iter().enumerate() {
+        if order.contains(&i) { continue; }
+        // A pending call that responded before this one was invoked must be linearized first.
+        if histories.iter().enumerate().any(|(j, h)| j != i && !order.contains(&j) && h.responded < history.invoked) { continue; }
+        if let Some(next) = step(state, &CALLS[history.call], &history.outcome) {
+            order.push(i);
+            if linearize(&next, histories, order, expected) { return true; }
+            order.pop();
+        }
+    }
+    false
+}
+
+async fn invoke(address: SocketAddr, password: &'static str, barrier: Arc<Barrier>, file: FileLocation, directories: Vec<FileLocation>, index: usize) -> anyhow::Result<History> {
+    let manager = WlistClientManager::new(address).await?;
+    let mut client = manager.get().await?;
+    let mut client = Some(&mut client);
+    let client = &mut client;
+    users_login(client, "admin".to_string(), password.to_string()).await?;
+
+    crate::core::rendezvous(&barrier).await?;
+    let invoked = Instant::now();
+    let result = match &CALLS[index] {
+        Call::Rename { name, policy } => api!(@client files_rename(client, file, name.to_string(), policy.duplicate())),
+        Call::Move { directory, policy } => api!(@client files_move(client, file, directories[*directory], policy.duplicate())),
+    };
+    let responded = Instant::now();
+    let outcome = match crate::may_error::<_, wlist_native::common::exceptions::DuplicateFileError>(result)? {
+        Some(information) => {
+            // Every call targets the original location, which relies on a file keeping its id (see the client module).
+            anyhow::ensure!(information.id == file.file_id, "{:?} changed the file id: {file:?} -> {information:?}", CALLS[index]);
+            Outcome::Ok {
+                directory: directories.iter().position(|d| d.file_id == information.parent_id)
+                    .ok_or_else(|| anyhow::anyhow!("{:?} moved the file out of the test directories: {information:?}", CALLS[index]))?,
+                name: information.name.as_str().to_string(),
+            }
+        },
+        None => Outcome::Duplicate,
+    };
+    Ok(History { call: index, invoked, responded, outcome })
+}
+
+async fn round(guard: &InitializeGuard, root: FileLocation, address: SocketAddr, index: usize) -> anyhow::Result<()> {
+    let base = api!(upload_mkdir(guard, root, format!("race-{index}"), Duplicate::Error))?;
+    let base = base.get_location(root.storage);
+    let mut directories = Vec::new();
+    for (i, blockers) in BLOCKERS.iter().enumerate() {
+        let directory = api!(upload_mkdir(guard, base, i.to_string(), Duplicate::Error))?;
+        let directory = directory.get_location(root.storage);
+        for blocker in blockers.iter() {
+            super::upload::upload(guard, directory, blocker.to_string(), Bytes::from_static(BLOCKER), Duplicate::Error).await?;
+        }
+        directories.push(directory);
+    }
+    let file = super::upload::upload(guard, directories[0], "race.txt".to_string(), Bytes::from_static(RACE), Duplicate::Error).await?;
+    let file = file.get_location(root.storage);
+    let initial = State {
+        directory: 0,
+        name: "race.txt".to_string(),
+        blockers: BLOCKERS.iter().map(|b| b.iter().map(|n| n.to_string()).collect()).collect(),
+    };
+
+    let barrier = Arc::new(Barrier::new(CALLS.len()));
+    let mut set = JoinSet::new();
+    for index in 0..CALLS.len() {
+        set.spawn(crate::in_current_test(invoke(address, guard.password, Arc::clone(&barrier), file, directories.clone(), index)));
+    }
+    let mut histories = Vec::new();
+    for r in set.join_all().await { histories.push(r?); }
+
+    // Found by size rather than name, since the raced file may replace a blocker of the same name.
+    let mut found = Vec::new();
+    let mut blockers = Vec::new();
+    for (i, directory) in directories.iter().enumerate() {
+        let list = super::list::list(guard, *directory, None).await?;
+        let mut names = BTreeSet::new();
+        for information in list.files {
+            if information.size == Some(RACE.len() as u64) {
+                found.push((i, information.name.as_str().to_string()));
+            } else {
+                names.insert(information.name.as_str().to_string());
+            }
+        }
+        blockers.push(names);
+    }
+    let [(directory, name)] = <[_; 1]>::try_from(found)
+        .map_err(|found| anyhow::anyhow!("expect exactly one raced file, found {found:?}.\nblockers: {blockers:?}\nhistories: {histories:#?}"))?;
+    let expected = State { directory, name, blockers };
+
+    let mut order = Vec::new();
+    if !linearize(&initial, &histories, &mut order, &expected) {
+        return Err(anyhow::anyhow!("no sequential order explains the concurrent calls.\nfinal: {expected:?}\nhistories: {histories:#?}"));
+    }
+    debug!(?order, ?expected, "Linearized.");
+
+    let information = api!(trash_trash(guard, base))?;
+    api!(trash_delete(guard, information.get_location(root.storage)))
+}
+
+pub async fn test_normal(guard: &InitializeGuard, root: FileLocation) -> anyhow::Result<()> {
+    let server = WlistServer::start("localhost:0").await?;
+    for i in 0..ROUNDS {
+        round(guard, root, server.local_addr(), i).await?;
+    }
+    info!(rounds = ROUNDS, "Concurrent rename/move linearized.");
+    server.stop().await?;
+    Ok(())
+}
@@ -3,61 +3,64 @@ use wlist_native::common::data::files::tokens::RefreshToken;
 use wlist_native::core::client::refresh::{refresh_cancel, refresh_check, refresh_confirm, refresh_is_paused, refresh_pause, refresh_progress, refresh_request, refresh_resume};
 use wlist_native::core::client::trash::trash_refresh;
 
-use crate::core::{c, InitializeGuard};
+use crate::core::{api, InitializeGuard};
 
 pub async fn test_none(guard: &InitializeGuard) -> anyhow::Result<()> {
-    let result = refresh_request(c!(guard), FileLocation { storage: 0, file_id: 0, is_directory: true, }).await;
+    let result = api!(refresh_request(guard, FileLocation { storage: 0, file_id: 0, is_directory: true, }));
     crate::assert_error::<_, wlist_native::common::exceptions::StorageNotFoundError>(result)?;
-    let result = refresh_request(c!(guard), FileLocation { storage: 0, file_id: 0, is_directory: false, }).await;
+    let result = api!(refresh_request(guard, FileLocation { storage: 0, file_id: 0, is_directory: false, }));
     crate::assert_error::<_, wlist_native::common::exceptions::IncorrectArgumentError>(result)?;
     Ok(())
 }
 
 pub async fn refresh(guard: &InitializeGuard, token: RefreshToken) -> anyhow::Result<()> {
-    refresh_confirm(c!(guard), token.clone()).await?;
+    let tracker = crate::Tracker::new(&token);
+    api!(refresh_confirm(guard, token.clone()))?;
     loop {
-        let result = refresh_progress(c!(guard), token.clone()).await;
+        let result = api!(refresh_progress(guard, token.clone()));
         let result = crate::may_error::<_, wlist_native::common::exceptions::TokenExpiredError>(result)?;
         let Some(progress) = result else { break };
         assert!(progress.loaded_files <= progress.total_files);
         assert!(progress.loaded_directories <= progress.total_directories);
         tracing::debug!(?progress, "refreshing");
+        tracker.update(&progress);
         tokio::time::sleep(std::time::Duration::from_millis(300)).await;
     }
-    let ok = refresh_check(c!(guard), token).await?;
+    drop(tracker);
+    let ok = api!(refresh_check(guard, token))?;
     assert_eq!(ok, true);
     Ok(())
 }
 
 pub async fn test_normal(guard: &InitializeGuard, root: FileLocation) -> anyhow::Result<()> {
     // refresh_test_normal
-    let confirmation = refresh_request(c!(guard), root).await?;
+    let confirmation = api!(refresh_request(guard, root))?;
     refresh(guard, confirmation.token).await?;
 
     // TODO: test pause
 
     // refresh_test_trash
-    let confirmation = trash_refresh(c!(guard), root.storage).await?;
+    let confirmation = api!(trash_refresh(guard, root.storage))?;
     refresh(guard, confirmation.token).await?;
     Ok(())
 }
 
 pub async fn test_empty(guard: &InitializeGuard, root: FileLocation) -> anyhow::Result<()> {
     // refresh_test_cancel
-    let confirmation = refresh_request(c!(guard), root).await?;
-    let result = refresh_pause(c!(guard), confirmation.token.clone()).await;
+    let confirmation = api!(refresh_request(guard, root))?;
+    let result = api!(refresh_pause(guard, confirmation.token.clone()));
     crate::assert_error::<_, wlist_native::common::exceptions::TokenExpiredError>(result)?;
-    let result = refresh_resume(c!(guard), confirmation.token.clone()).await;
+    let result = api!(refresh_resume(guard, confirmation.token.clone()));
     crate::assert_error::<_, wlist_native::common::exceptions::TokenExpiredError>(result)?;
-    let result = refresh_is_paused(c!(guard), confirmation.token.clone()).await;
+    let result = api!(refresh_is_paused(guard, confirmation.token.clone()));
     crate::assert_error::<_, wlist_native::common::exceptions::TokenExpiredError>(result)?;
-    let result = refresh_progress(c!(guard), confirmation.token.clone()).await;
+    let result = api!(refresh_progress(guard, confirmation.token.clone()));
     crate::assert_error::<_, wlist_native::common::exceptions::TokenExpiredError>(result)?;
-    let result = refresh_check(c!(guard), confirmation.token.clone()).await;
+    let result = api!(refresh_check(guard, confirmation.token.clone()));
     crate::assert_error::<_, wlist_native::common::exceptions::TokenExpiredError>(result)?;
-    refresh_cancel(c!(guard), confirmation.token).await?;
+    api!(refresh_cancel(guard, confirmation.token))?;
 
-    let confirmation = refresh_request(c!(guard), root).await?;
+    let confirmation = api!(refresh_request(guard, root))?;
     refresh(guard, confirmation.token).await?;
     Ok(())
 }
@@ -0,0 +1,94 @@
+use bytes::BytesMut;
+use rand::Rng;
+use tokio::sync::watch::channel;
+use tracing::{info, warn};
+
+use wlist_native::common::data::files::options::Duplicate;
+use wlist_native::common::data::files::FileLocation;
+use wlist_native::core::client::download::download_request;
+use wlist_native::core::client::files::files_get;
+use wlist_native::core::client::trash::{trash_delete, trash_trash};
+use wlist_native::core::client::upload::{upload_cancel, upload_confirm, upload_extra_md5s, upload_finish, upload_request, upload_stream};
+use wlist_native::core::client::users::users_login;
+use wlist_native::core::client::WlistClientManager;
+use wlist_native::core::helper::hasher::Md5Hasher;
+use wlist_native::core::server::WlistServer;
+
+use crate::core::{api, InitializeGuard};
+
+pub async fn test_normal(guard: &InitializeGuard, root: FileLocation) -> anyhow::Result<()> {
+    let server = WlistServer::start("localhost:0").await?;
+    let address = server.local_addr();
+
+    let chunk = api!(upload_extra_md5s(guard, root.storage))?.map(|chunk| chunk.get());
+    let mut data = BytesMut::zeroed(chunk.unwrap_or(1 << 20) * 4 + 7);
+    rand::thread_rng().fill(&mut data[..]);
+    let data = data.freeze();
+    let (md5, md5s) = super::upload::hash(&data, chunk).await;
+
+    // Stream the first half of the chunks, then drop the connection.
+    let (token, chunks) = {
+        let manager = WlistClientManager::new(address).await?;
+        let mut client = manager.get().await?;
+        let mut client = Some(&mut client);
+        let client = &mut client;
+        users_login(client, "admin".to_string(), guard.password.to_string()).await?;
+
+        let confirmation = api!(@client upload_request(client, root, "Resume.bin".to_string(), data.len() as u64, md5.clone(), md5s, Duplicate::Error))?;
+        assert_eq!(confirmation.done, false); // Random data never hits.
+        let information = api!(@client upload_confirm(client, confirmation.token.clone()))?;
+        for (chunk, id) in information.chunks.iter().take(information.chunks.len() / 2).zip(0..) {
+            let mut buf = data.slice(chunk.start as usize..(chunk.start + chunk.size) as usize);
+            api!(@client upload_stream(client, confirmation.token.clone(), id, &mut buf, channel(0).0, channel(true).1))?;
+        }
+        (confirmation.token, information.chunks)
+    };
+    info!(chunks = chunks.len(), streamed = chunks.len() / 2, "Reconnecting to resume the upload.");
+
+    // Continue with the same token on a new connection.
+    let result = async {
+        let manager = WlistClientManager::new(address).await?;
+        let mut client = manager.get().await?;
+        let mut client = Some(&mut client);
+        let client = &mut client;
+        users_login(client, "admin".to_string(), guard.password.to_string()).await?;
+        for (chunk, id) in chunks.iter().zip(0..).skip(chunks.len() / 2) {
+            let mut buf = data.slice(chunk.start as usize..(chunk.start + chunk.size) as usize);
+            api!(@client upload_stream(client, token.clone(), id, &mut buf, channel(0).0, channel(true).1))?;
+        }
+        api!(@client upload_finish(client, token.clone()))
+    }.await;
+
+    match crate::may_error::<_, wlist_native::common::exceptions::TokenExpiredError>(result)? {
+        Some(information) => {
+            // resume_test_survived
+            info!("Upload token survived the reconnection.");
+            assert_eq!(information.name.as_str(), "Resume.bin");
+            assert_eq!(information.size, Some(data.len() as u64));
+            let location = information.get_location(root.storage);
+            let details = api!(files_get(guard, location, false, false))?;
+            super::get::assert_md5(Some(md5.as_str()), &details);
+            super::get::close_thumbnail(guard, &details).await?;
+
+            let confirmation = api!(download_request(guard, location, 0, u64::MAX))?;
+            let (downloaded, _, _) = super::download::download0(guard, &confirmation.token).await?;
+            let hasher = Md5Hasher::new();
+            hasher.update(downloaded).await;
+            assert_eq!(hasher.finalize().await, md5);
+
+            let information = api!(trash_trash(guard, location))?;
+            api!(trash_delete(guard, information.get_location(root.storage)))?;
+        },
+        None => {
+            // resume_test_expired
+            warn!("Upload token expired with the connection.");
+            let result = api!(upload_cancel(guard, token));
+            crate::may_error::<_, wlist_native::common::exceptions::TokenExpiredError>(result)?;
+            let list = super::list::list(guard, root, None).await?;
+            assert!(list.files.iter().all(|i| i.name.as_str() != "Resume.bin"), "{:?}", list);
+        },
+    }
+
+    server.stop().await?;
+    Ok(())
+}
@@ -0,0 +1,97 @@
+use anyhow::Context;
+use bytes::{Bytes, BytesMut};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use tracing::{info, warn};
+
+use wlist_native::common::data::files::options::Duplicate;
+use wlist_native::common::data::files::FileLocation;
+use wlist_native::common::data::storages::StorageType;
+use wlist_native::core::client::download::download_request;
+use wlist_native::core::client::storages::storages_get;
+use wlist_native::core::client::trash::{trash_delete, trash_trash};
+use wlist_native::core::client::upload::{upload_extra_md5s, upload_request};
+
+use crate::core::{api, InitializeGuard};
+
+/// Cases that failed (or panicked) are kept here as `size seed` lines and replayed first on the next run.
+const SEEDS: &str = "run/upload_roundtrip_seeds.txt";
+const RANDOM_CASES: usize = 16;
+/// Most chunk md5s test_too_large sends in one request, about 32 MiB of hex.
+const MAX_MD5S: u64 = 1 << 20;
+
+fn load_seeds() -> Vec<(usize, u64)> {
+    let Ok(content) = std::fs::read_to_string(SEEDS) else { return Vec::new() };
+    content.lines().filter_map(|line| {
+        let (size, seed) = line.split_once(' ')?;
+        Some((size.parse().ok()?, seed.parse().ok()?))
+    }).collect()
+}
+
+fn save_seeds(seeds: &[(usize, u64)]) -> anyhow::Result<()> {
+    let content = seeds.iter().map(|(size, seed)| format!("{size} {seed}\n")).collect::<String>();
+    Ok(std::fs::write(SEEDS, content)?)
+}
+
+fn generate(size: usize, seed: u64) -> Bytes {
+    let mut bytes = BytesMut::zeroed(size);
+    StdRng::seed_from_u64(seed).fill(&mut bytes[..]);
+    bytes.freeze()
+}
+
+async fn roundtrip(guard: &InitializeGuard, root: FileLocation, size: usize, seed: u64) -> anyhow::Result<()> {
+    let data = generate(size, seed);
+    let file = super::upload::upload(guard, root, format!("RoundTrip-{size}-{seed}.bin"), data.clone(), Duplicate::Error).await?;
+    let result = async {
+        let confirmation = api!(download_request(guard, file.get_location(root.storage), 0, u64::MAX))?;
+        anyhow::ensure!(confirmation.size == size as u64, "confirmation.size {} != {size}", confirmation.size);
+        let (downloaded, from, to) = super::download::download0(guard, &confirmation.token).await?;
+        anyhow::ensure!(from == 0 && to == size as u64, "downloaded range {from}..{to} != 0..{size}");
+        if let Some(i) = data.iter().zip(downloaded.iter()).position(|(a, b)| a != b) {
+            anyhow::bail!("data differs from downloaded at byte {i}");
+        }
+        anyhow::ensure!(data.len() == downloaded.len(), "downloaded {} bytes, expected {size}", downloaded.len());
+        Ok::<_, anyhow::Error>(())
+    }.await;
+    let information = api!(trash_trash(guard, file.get_location(root.storage)))?;
+    api!(trash_delete(guard, information.get_location(root.storage)))?;
+    result
+}
+
+pub async fn test_normal(guard: &InitializeGuard, root: FileLocation) -> anyhow::Result<()> {
+    let chunk = api!(upload_extra_md5s(guard, root.storage))?.map(|c| c.get()).unwrap_or(1 << 20);
+    let information = api!(storages_get(guard, root.storage, false))?;
+    let (storage, max) = (information.basic.storage_type, information.max_size_per_file);
+
+    let mut seeds = load_seeds();
+    let mut cases = seeds.clone();
+    let mut rand = rand::thread_rng();
+    let sizes = [0, 1, chunk - 1, chunk, chunk + 1, 2 * chunk - 1, 2 * chunk, 2 * chunk + 1, 3 * chunk]
+        .into_iter().chain((0..RANDOM_CASES).map(|_| rand.gen_range(0..3 * chunk)));
+    cases.extend(sizes.filter(|size| *size as u64 <= max).map(|size| (size, rand.gen())));
+    for (size, seed) in cases {
+        if !seeds.contains(&(size, seed)) {
+            seeds.push((size, seed));
+            save_seeds(&seeds)?;
+        }
+        info!(%size, %seed, "Round trip.");
+        roundtrip(guard, root, size, seed).await
+            .with_context(|| format!("round trip failed: size={size} seed={seed}, saved to {SEEDS}"))?;
+        seeds.retain(|c| *c != (size, seed));
+        save_seeds(&seeds)?;
+    }
+
+    // test_too_large
+    let size = max.saturating_add(1);
+    let count = size.div_ceil(chunk as u64);
+    if size <= max || count > MAX_MD5S {
+        // The suite configures the mocker, so there it must leave room to test the limit.
+        anyhow::ensure!(storage != StorageType::Mocker, "test_too_large cannot run on the mocker: max_size_per_file {max} needs {count} md5s, lower it in its account");
+        warn!(?storage, %max, %count, "Skipped test_too_large: no size over max_size_per_file can be requested.");
+        return Ok(());
+    }
+    let md5 = super::upload::generate_md5();
+    let result = api!(upload_request(guard, root, "TooLarge.bin".to_string(), size, md5.clone(), Some(vec![md5; count as usize]), Duplicate::Error));
+    crate::assert_error::<_, wlist_native::common::exceptions::FileTooLargeError>(result)?;
+    Ok(())
+}
@@ -2,14 +2,14 @@ use wlist_native::common::data::storages::information::{StorageInformation, Stor
 use wlist_native::common::data::storages::options::{ListStorageOptions, StoragesFilter};
 use wlist_native::core::client::storages::{storages_get, storages_list, storages_rename, storages_set_readonly};
 
-use crate::core::c;
+use crate::core::api;
 
 async fn test_list(guard: &super::InitializeGuard, filter: StoragesFilter, not_filtered: bool, info: &StorageInformation) -> anyhow::Result<()> {
-    let list = storages_list(c!(guard), ListStorageOptions {
+    let list = api!(storages_list(guard, ListStorageOptions {
         filter, orders: Default::default(),
         offset: 0,
         limit: 2,
-    }).await?;
+    }))?;
     assert_eq!(list.total, 1);
     if not_filtered {
         assert_eq!(list.filtered, 1);
@@ -33,12 +33,12 @@ pub async fn list(guard: &super::InitializeGuard, info: &StorageInformation) ->
 
     // test_offset
     assert_eq!(
-        storages_list(c!(guard), ListStorageOptions {
+        api!(storages_list(guard, ListStorageOptions {
             filter: StoragesFilter::All,
             orders: Default::default(),
             offset: 1,
             limit: 1,
-        }).await?,
+        }))?,
         StorageListInformation {
             total: 1,
             filtered: 1,
@@ -47,12 +47,12 @@ pub async fn list(guard: &super::InitializeGuard, info: &StorageInformation) ->
     );
     // test_limit
     assert_eq!(
-        storages_list(c!(guard), ListStorageOptions {
+        api!(storages_list(guard, ListStorageOptions {
             filter: StoragesFilter::All,
             orders: Default::default(),
             offset: 0,
             limit: 0,
-        }).await?,
+        }))?,
         StorageListInformation {
             total: 1,
             filtered: 1,
@@ -63,17 +63,17 @@ pub async fn list(guard: &super::InitializeGuard, info: &StorageInformation) ->
 }
 
 pub async fn get(guard: &super::InitializeGuard, info: &StorageInformation) -> anyhow::Result<()> {
-    let detail = storages_get(c!(guard), info.id, false).await?;
+    let detail = api!(storages_get(guard, info.id, false))?;
     assert_eq!(&detail.basic, info);
     assert_eq!(detail.indexed_size, 0); // Newly created storage has no indexed data
     tracing::debug!(?info, ?detail, "Got storage detail.");
 
-    let checked = storages_get(c!(guard), info.id, true).await?;
+    let checked = api!(storages_get(guard, info.id, true))?;
     assert_eq!(checked, detail); // Nothing changed after checked.
 
-    let result = storages_get(c!(guard), 0, false).await;
+    let result = api!(storages_get(guard, 0, false));
     crate::assert_error::<_, wlist_native::common::exceptions::StorageNotFoundError>(result)?;
-    let result = storages_get(c!(guard), 0, true).await;
+    let result = api!(storages_get(guard, 0, true));
     crate::assert_error::<_, wlist_native::common::exceptions::StorageNotFoundError>(result)?;
     Ok(())
 }
@@ -81,9 +81,9 @@ pub async fn get(guard: &super::InitializeGuard, info: &StorageInformation) -> a
 pub async fn rename(guard: &super::InitializeGuard, info: &StorageInformation) -> anyhow::Result<StorageInformation> {
     // test_rename_invalid
     for new_name in super::INVALID_STORAGE_NAME.iter() {
-        let result = storages_rename(c!(guard), info.id, new_name.clone()).await;
+        let result = api!(storages_rename(guard, info.id, new_name.clone()));
         crate::assert_error::<_, wlist_native::common::exceptions::IncorrectArgumentError>(result)?;
-        let result = storages_rename(c!(guard), 0, new_name.clone()).await;
+        let result = api!(storages_rename(guard, 0, new_name.clone()));
         crate::assert_error::<_, wlist_native::common::exceptions::IncorrectArgumentError>(result)?;
     }
 
@@ -91,8 +91,8 @@ pub async fn rename(guard: &super::InitializeGuard, info: &StorageInformation) -
     let name = info.name.to_string();
     let mut info = info.clone();
     for new_name in super::VALID_STORAGE_NAME.iter().chain(std::iter::once(&name)) {
-        storages_rename(c!(guard), info.id, new_name.clone()).await?;
-        let detail = storages_get(c!(guard), info.id, false).await?;
+        api!(storages_rename(guard, info.id, new_name.clone()))?;
+        let detail = api!(storages_get(guard, info.id, false))?;
         assert_eq!(detail.basic.name.as_str(), new_name);
         assert_eq!(detail.basic.id, info.id);
         assert_eq!(detail.basic.read_only, info.read_only);
@@ -105,8 +105,8 @@ pub async fn rename(guard: &super::InitializeGuard, info: &StorageInformation) -
     }
 
     // test_rename_duplicate
-    storages_rename(c!(guard), info.id, name.clone()).await?;
-    let detail = storages_get(c!(guard), info.id, false).await?;
+    api!(storages_rename(guard, info.id, name.clone()))?;
+    let detail = api!(storages_get(guard, info.id, false))?;
     assert_eq!(detail.basic.name.as_str(), name);
     assert_eq!(detail.basic.id, info.id);
     assert_eq!(detail.basic.read_only, info.read_only);
@@ -121,22 +121,22 @@ pub async fn rename(guard: &super::InitializeGuard, info: &StorageInformation) -
 pub async fn set_readonly(guard: &super::InitializeGuard, info: &StorageInformation) -> anyhow::Result<StorageInformation> {
     let info = if info.storage_type.is_share() {
         // test_set_readonly_invalid
-        let result = storages_set_readonly(c!(guard), info.id, false).await;
+        let result = api!(storages_set_readonly(guard, info.id, false));
         crate::assert_error::<_, wlist_native::common::exceptions::StorageTypeMismatchedError>(result)?;
 
         // test_set_readonly_twice
-        storages_set_readonly(c!(guard), info.id, true).await?;
-        let detail = storages_get(c!(guard), info.id, false).await?;
+        api!(storages_set_readonly(guard, info.id, true))?;
+        let detail = api!(storages_get(guard, info.id, false))?;
         assert_eq!(&detail.basic, info);
         detail.basic
     } else {
         // test_set_readonly_twice
-        storages_set_readonly(c!(guard), info.id, false).await?;
-        let detail = storages_get(c!(guard), info.id, false).await?;
+        api!(storages_set_readonly(guard, info.id, false))?;
+        let detail = api!(storages_get(guard, info.id, false))?;
         assert_eq!(&detail.basic, info);
 
-        storages_set_readonly(c!(guard), info.id, true).await?;
-        let detail_readonly = storages_get(c!(guard), info.id, false).await?;
+        api!(storages_set_readonly(guard, info.id, true))?;
+        let detail_readonly = api!(storages_get(guard, info.id, false))?;
         test_list(guard, StoragesFilter::Readonly, true, &detail_readonly.basic).await?;
         test_list(guard, StoragesFilter::Writable, false, &detail_readonly.basic).await?;
         test_list(guard, StoragesFilter::Shared, false, &detail_readonly.basic).await?;
@@ -159,8 +159,8 @@ pub async fn set_readonly(guard: &super::InitializeGuard, info: &StorageInformat
         assert_eq!(detail_readonly.download_flow, detail.download_flow);
         assert_eq!(detail_readonly.max_size_per_file, detail.max_size_per_file);
 
-        storages_set_readonly(c!(guard), info.id, false).await?;
-        let detail_revert = storages_get(c!(guard), info.id, false).await?;
+        api!(storages_set_readonly(guard, info.id, false))?;
+        let detail_revert = api!(storages_get(guard, info.id, false))?;
         test_list(guard, StoragesFilter::Readonly, false, &detail_revert.basic).await?;
         test_list(guard, StoragesFilter::Writable, true, &detail_revert.basic).await?;
         test_list(guard, StoragesFilter::Shared, false, &detail_revert.basic).await?;
@@ -186,9 +186,9 @@ pub async fn set_readonly(guard: &super::InitializeGuard, info: &StorageInformat
         detail_revert.basic
     };
 
-    let result = storages_set_readonly(c!(guard), 0, false).await;
+    let result = api!(storages_set_readonly(guard, 0, false));
     crate::assert_error::<_, wlist_native::common::exceptions::StorageNotFoundError>(result)?;
-    let result = storages_set_readonly(c!(guard), 0, true).await;
+    let result = api!(storages_set_readonly(guard, 0, true));
     crate::assert_error::<_, wlist_native::common::exceptions::StorageNotFoundError>(result)?;
     Ok(info)
 }
@@ -0,0 +1,296 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+
+use bytes::Bytes;
+use indexmap::IndexMap;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use tokio::sync::watch::channel;
+use tokio::task::JoinSet;
+use tracing::{debug, info};
+
+use wlist_native::common::data::files::information::FileInformation;
+use wlist_native::common::data::files::options::{Duplicate, FilesFilter, FilesOrder, ListFileOptions};
+use wlist_native::common::data::files::FileLocation;
+use wlist_native::common::data::Direction;
+use wlist_native::core::client::files::{files_list, files_move, files_rename};
+use wlist_native::core::client::trash::{trash_delete, trash_trash};
+use wlist_native::core::client::upload::{upload_confirm, upload_extra_md5s, upload_finish, upload_mkdir, upload_request, upload_stream};
+use wlist_native::core::client::users::users_login;
+use wlist_native::core::client::{WlistClient, WlistClientManager};
+use wlist_native::core::helper::hasher::Md5Hasher;
+use wlist_native::core::server::WlistServer;
+
+use crate::core::{api, InitializeGuard};
+
+const WORKERS: usize = 8;
+const OPERATIONS: usize = 32;
+
+#[derive(Debug, Clone)]
+enum Operation {
+    Upload { parent: i64, name: String, size: usize },
+    Mkdir { parent: i64, name: String },
+    Rename { id: i64, is_directory: bool, name: String },
+    Move { id: i64, is_directory: bool, parent: i64 },
+    Trash { id: i64, is_directory: bool },
+    List { directory: i64 },
+}
+
+#[derive(Debug)]
+struct Record {
+    worker: usize,
+    operation: Operation,
+    /// The id of the affected file after the operation.
+    result: Result<i64, String>,
+    /// Whether the operation failed with an error [rejected] does not allow.
+    unexpected: bool,
+}
+
+/// Rejections a valid operation may still get: a duplicate name, or a move the storage refuses as too complex.
+fn rejected(e: &anyhow::Error) -> bool {
+    e.downcast_ref::<wlist_native::common::exceptions::DuplicateFileError>().is_some()
+        || e.downcast_ref::<wlist_native::common::exceptions::ComplexOperationError>().is_some()
+}
+
+#[derive(Debug, Clone)]
+struct Node {
+    parent: i64,
+    name: String,
+    is_directory: bool,
+}
+
+async fn upload(client: &mut Option<&mut WlistClient<'_>>, parent: FileLocation, name: String, data: Bytes) -> anyhow::Result<FileInformation> {
+    let md5 = Md5Hasher::new();
+    md5.update(data.clone()).await;
+    let md5 = md5.finalize().await;
+    // The data is always smaller than a chunk, so there is only one extra md5.
+    let md5s = api!(@client upload_extra_md5s(client, parent.storage))?.map(|_| vec![md5.clone()]);
+    let confirmation = api!(@client upload_request(client, parent, name, data.len() as u64, md5, md5s, Duplicate::Error))?;
+    if !confirmation.done {
+        let information = api!(@client upload_confirm(client, confirmation.token.clone()))?;
+        for (chunk, id) in information.chunks.into_iter().zip(0..) {
+            let l = chunk.start as usize;
+            let r = l + chunk.size as usize;
+            let mut data = data.slice(l..r);
+            api!(@client upload_stream(client, confirmation.token.clone(), id, &mut data, channel(0).0, channel(true).1))?;
+        }
+    }
+    api!(@client upload_finish(client, confirmation.token))
+}
+
+/// Returns true if `id` is `ancestor` or lies under it.
+fn is_under(nodes: &BTreeMap<i64, Node>, mut id: i64, ancestor: i64) -> bool {
+    loop {
+        if id == ancestor { return true; }
+        match nodes.get(&id) {
+            Some(node) => id = node.parent,
+            None => return false,
+        }
+    }
+}
+
+fn remove_recursively(nodes: &mut BTreeMap<i64, Node>, id: i64) {
+    let children = nodes.iter().filter(|(_, n)| n.parent == id).map(|(i, _)| *i).collect::<Vec<_>>();
+    for child in children {
+        remove_recursively(nodes, child);
+    }
+    nodes.remove(&id);
+}
+
+fn apply(nodes: &mut BTreeMap<i64, Node>, operation: &Operation, id: i64) {
+    match operation {
+        Operation::Upload { parent, name, .. } => {
+            nodes.insert(id, Node { parent: *parent, name: name.clone(), is_directory: false });
+        },
+        Operation::Mkdir { parent, name } => {
+            nodes.insert(id, Node { parent: *parent, name: name.clone(), is_directory: true });
+        },
+        Operation::Rename { id: old, name, .. } => if let Some(mut node) = nodes.remove(old) {
+            node.name = name.clone();
+            nodes.insert(id, node);
+            nodes.values_mut().filter(|n| n.parent == *old).for_each(|n| n.parent = id);
+        },
+        Operation::Move { id: old, parent, .. } => if let Some(mut node) = nodes.remove(old) {
+            node.parent = *parent;
+            nodes.insert(id, node);
+            nodes.values_mut().filter(|n| n.parent == *old).for_each(|n| n.parent = id);
+        },
+        Operation::Trash { id, .. } => remove_recursively(nodes, *id),
+        Operation::List { .. } => {},
+    }
+}
+
+fn path(nodes: &BTreeMap<i64, Node>, mut id: i64, base: i64) -> Option<String> {
+    let mut names = Vec::new();
+    while id != base {
+        let node = nodes.get(&id)?;
+        names.push(node.name.as_str());
+        id = node.parent;
+    }
+    names.reverse();
+    Some(names.join("/"))
+}
+
+fn choose<T: Copy>(rand: &mut StdRng, items: &[T]) -> Option<T> {
+    if items.is_empty() { None } else { Some(items[rand.gen_range(0..items.len())]) }
+}
+
+/// Every worker may upload, mkdir and move into directories in `shared`, whoever created them.
+/// A shared directory is never renamed, moved or trashed, and neither are its ancestors, so its id stays valid for every worker.
+async fn worker(address: SocketAddr, password: &'static str, base: FileLocation, index: usize, seed: u64, shared: Arc<Mutex<Vec<i64>>>, log: Arc<Mutex<Vec<Record>>>) -> anyhow::Result<()> {
+    let manager = WlistClientManager::new(address).await?;
+    let mut client = manager.get().await?;
+    let mut client = Some(&mut client);
+    let client = &mut client;
+    users_login(client, "admin".to_string(), password.to_string()).await?;
+
+    let storage = base.storage;
+    let mut rand = StdRng::seed_from_u64(seed);
+    // Only this worker touches its own entries, so this view of them is exact.
+    let mut nodes: BTreeMap<i64, Node> = BTreeMap::new();
+    let mut published = BTreeSet::new();
+    for i in 0..OPERATIONS {
+        let directories = std::iter::once(base.file_id)
+            .chain(shared.lock().unwrap().iter().copied())
+            .chain(nodes.iter().filter(|(_, n)| n.is_directory).map(|(i, _)| *i))
+            .collect::<BTreeSet<_>>().into_iter().collect::<Vec<_>>();
+        let entries = nodes.keys().copied().filter(|id| !published.contains(id)).collect::<Vec<_>>();
+        let location = |id: i64, is_directory: bool| FileLocation { storage, file_id: id, is_directory };
+        let operation = match rand.gen_range(0..6) {
+            0 => Operation::Upload { parent: choose(&mut rand, &directories).unwrap(), name: format!("w{index}-{i}.txt"), size: rand.gen_range(0..64) },
+            1 => Operation::Mkdir { parent: choose(&mut rand, &directories).unwrap(), name: format!("w{index}-{i}") },
+            2 => match choose(&mut rand, &entries) {
+                Some(id) => Operation::Rename { id, is_directory: nodes[&id].is_directory, name: format!("w{index}-{i}-renamed") },
+                None => continue,
+            },
+            3 => match choose(&mut rand, &entries) {
+                Some(id) => {
+                    let targets = directories.iter().copied().filter(|d| *d != nodes[&id].parent && !is_under(&nodes, *d, id)).collect::<Vec<_>>();
+                    let Some(parent) = choose(&mut rand, &targets) else { continue };
+                    Operation::Move { id, is_directory: nodes[&id].is_directory, parent }
+                },
+                None => continue,
+            },
+            4 => match choose(&mut rand, &entries) {
+                Some(id) => Operation::Trash { id, is_directory: nodes[&id].is_directory },
+                None => continue,
+            },
+            _ => Operation::List { directory: choose(&mut rand, &directories).unwrap() },
+        };
+        debug!(%index, ?operation, "Stress operation.");
+        let result = match &operation {
+            Operation::Upload { parent, name, size } => {
+                let data = (0..*size).map(|_| rand.gen()).collect::<Vec<u8>>();
+                upload(client, location(*parent, true), name.clone(), Bytes::from(data)).await.map(|i| i.id)
+            },
+            Operation::Mkdir { parent, name } =>
+                api!(@client upload_mkdir(client, location(*parent, true), name.clone(), Duplicate::Error)).map(|i| i.id),
+            Operation::Rename { id, is_directory, name } =>
+                api!(@client files_rename(client, location(*id, *is_directory), name.clone(), Duplicate::Error)).map(|i| i.id),
+            Operation::Move { id, is_directory, parent } =>
+                api!(@client files_move(client, location(*id, *is_directory), location(*parent, true), Duplicate::Error)).map(|i| i.id),
+            Operation::Trash { id, is_directory } =>
+                api!(@client trash_trash(client, location(*id, *is_directory))).map(|i| i.id),
+            Operation::List { directory } => api!(@client files_list(client, location(*directory, true), ListFileOptions {
+                filter: FilesFilter::Both, orders: Default::default(), offset: 0, limit: 0,
+            })).map(|_| *directory),
+        };
+        // A file keeps its id (see the client module), so only directories are remapped by [apply].
+        let result = result.and_then(|new| match &operation {
+            Operation::Rename { id, is_directory: false, .. } | Operation::Move { id, is_directory: false, .. } if new != *id =>
+                Err(anyhow::anyhow!("the file id changed from {id} to {new}")),
+            _ => Ok(new),
+        });
+        if let Ok(id) = &result {
+            apply(&mut nodes, &operation, *id);
+        }
+        // Publish only directories whose ancestors are all frozen, and only after the mkdir is logged.
+        let publish = match (&operation, &result) {
+            (Operation::Mkdir { parent, .. }, Ok(id)) if rand.gen_bool(0.5) && (*parent == base.file_id || shared.lock().unwrap().contains(parent)) => Some(*id),
+            _ => None,
+        };
+        let unexpected = result.as_ref().is_err_and(|e| !rejected(e));
+        log.lock().unwrap().push(Record { worker: index, operation, result: result.map_err(|e| format!("{e:?}")), unexpected });
+        if unexpected { break; } // The view of this worker may be wrong from now on.
+        if let Some(id) = publish {
+            published.insert(id);
+            shared.lock().unwrap().push(id);
+        }
+    }
+    Ok(())
+}
+
+/// Lists the whole tree under `directory` as `(path, is_directory)`.
+async fn tree(guard: &InitializeGuard, directory: FileLocation, prefix: &str, tree: &mut BTreeSet<(String, bool)>) -> anyhow::Result<()> {
+    let list = super::list::list(guard, directory, Some(ListFileOptions {
+        filter: FilesFilter::Both, orders: IndexMap::from([(FilesOrder::Name, Direction::ASCEND)]), offset: 0, limit: 1 << 10,
+    })).await?;
+    assert_eq!(list.files.len() as u64, (list.total_file + list.total_directory) as u64);
+    for file in list.files {
+        let path = format!("{prefix}{}", file.name.as_str());
+        if file.is_directory {
+            Box::pin(self::tree(guard, file.get_location(directory.storage), &format!("{path}/"), tree)).await?;
+        }
+        tree.insert((path, file.is_directory));
+    }
+    Ok(())
+}
+
+pub async fn test_normal(guard: &InitializeGuard, root: FileLocation) -> anyhow::Result<()> {
+    let base = api!(upload_mkdir(guard, root, "stress".to_string(), Duplicate::Error))?;
+    let base = base.get_location(root.storage);
+
+    let server = WlistServer::start("localhost:0").await?;
+    let address = server.local_addr();
+    // The seed fixes the choices of each worker, but not how the workers interleave.
+    let seed = std::env::var("WLIST_TEST_STRESS_SEED").ok().and_then(|s| s.parse().ok()).unwrap_or_else(|| rand::thread_rng().gen::<u64>());
+    info!(%seed, workers = WORKERS, operations = OPERATIONS, "Starting stress test.");
+    let shared = Arc::new(Mutex::new(Vec::new()));
+    let log = Arc::new(Mutex::new(Vec::new()));
+    let mut set = JoinSet::new();
+    for index in 0..WORKERS {
+        set.spawn(crate::in_current_test(worker(address, guard.password, base, index, seed.wrapping_add(index as u64), Arc::clone(&shared), Arc::clone(&log))));
+    }
+    for r in set.join_all().await { r?; }
+    server.stop().await?;
+    let log = Arc::try_unwrap(log).unwrap().into_inner().unwrap();
+    if let Some(record) = log.iter().find(|r| r.unexpected) {
+        let history = log.iter().map(|r| format!("  {r:?}")).collect::<Vec<_>>().join("\n");
+        return Err(anyhow::anyhow!("stress operation failed unexpectedly (seed {seed}): {record:?}\noperations:\n{history}"));
+    }
+
+    // Merge all successful operations in the order they completed.
+    let mut nodes = BTreeMap::new();
+    for record in &log {
+        if let Ok(id) = &record.result {
+            apply(&mut nodes, &record.operation, *id);
+        }
+    }
+    let expected = nodes.iter()
+        .map(|(id, node)| path(&nodes, *id, base.file_id).map(|p| (p, node.is_directory)).ok_or(*id))
+        .collect::<Result<BTreeSet<_>, _>>()
+        .map_err(|id| anyhow::anyhow!("model entry {id} is detached from the stress root (seed {seed})"))?;
+    let mut actual = BTreeSet::new();
+    tree(guard, base, "", &mut actual).await?;
+
+    if expected != actual {
+        let lost = expected.difference(&actual).collect::<Vec<_>>();
+        let phantom = actual.difference(&expected).collect::<Vec<_>>();
+        let mut workers = BTreeSet::new();
+        for (path, _) in lost.iter().chain(phantom.iter()) {
+            for name in path.split('/') {
+                if let Some(worker) = name.strip_prefix('w').and_then(|n| n.split('-').next()).and_then(|n| n.parse::<usize>().ok()) {
+                    workers.insert(worker);
+                }
+            }
+        }
+        let history = log.iter().filter(|r| workers.contains(&r.worker))
+            .map(|r| format!("  {r:?}")).collect::<Vec<_>>().join("\n");
+        return Err(anyhow::anyhow!("stress tree mismatched (seed {seed}).\nlost: {lost:?}\nphantom: {phantom:?}\noperations:\n{history}"));
+    }
+    info!(operations = log.len(), entries = actual.len(), "Stress test passed.");
+
+    let information = api!(trash_trash(guard, base))?;
+    api!(trash_delete(guard, information.get_location(root.storage)))
+}
@@ -0,0 +1,54 @@
+use bytes::Bytes;
+
+use wlist_native::common::data::files::options::Duplicate;
+use wlist_native::common::data::files::FileLocation;
+use wlist_native::core::client::files::files_get;
+use wlist_native::core::client::trash::{trash_delete, trash_trash};
+
+use crate::core::{api, InitializeGuard};
+
+use super::fixtures::{HEIGHT, WIDTH};
+
+/// Uploads `data` as `name`, and returns the decoded thumbnail if the server generates one.
+async fn thumbnail(guard: &InitializeGuard, root: FileLocation, name: &str, data: Bytes) -> anyhow::Result<Option<image::DynamicImage>> {
+    let information = super::upload::upload(guard, root, name.to_string(), data, Duplicate::Error).await?;
+    let location = information.get_location(root.storage);
+    let details = api!(files_get(guard, location, false, false))?;
+    let result = match details.thumbnail.as_ref() {
+        None => Ok(None),
+        Some(thumbnail) => match super::download::download0(guard, &thumbnail.token).await {
+            Ok((data, _, _)) => image::load_from_memory(&data).map(Some).map_err(|e| anyhow::anyhow!("{name}: invalid thumbnail: {e}")),
+            Err(e) => Err(e),
+        },
+    };
+    let information = api!(trash_trash(guard, location))?;
+    api!(trash_delete(guard, information.get_location(root.storage)))?;
+    result
+}
+
+fn assert_sane(name: &str, thumbnail: Option<image::DynamicImage>) {
+    let thumbnail = thumbnail.unwrap_or_else(|| panic!("{name}: no thumbnail"));
+    let (width, height) = (thumbnail.width(), thumbnail.height());
+    assert!(0 < width && width <= WIDTH, "{name}: thumbnail width {width}");
+    assert!(0 < height && height <= HEIGHT, "{name}: thumbnail height {height}");
+}
+
+pub async fn test_normal(guard: &InitializeGuard, root: FileLocation) -> anyhow::Result<()> {
+    for fixture in super::fixtures::fixtures()?.into_iter().filter(|f| f.media) {
+        let name = format!("Thumbnail-{}", fixture.name);
+        let thumbnail = thumbnail(guard, root, &name, fixture.data).await?;
+        assert_sane(&name, thumbnail);
+    }
+
+    let text = thumbnail(guard, root, "Thumbnail.txt", Bytes::from_static(b"no thumbnail")).await?;
+    assert!(text.is_none(), "Thumbnail.txt: {text:?}");
+
+    let list = super::list::list(guard, root, None).await?;
+    for (i, name) in [(0, "chunk.txt"), (3, "large.txt")] {
+        let location = FileLocation { storage: root.storage, file_id: list.files[i].id, is_directory: false, };
+        let information = api!(files_get(guard, location, false, false))?;
+        assert_eq!(information.basic.name.as_str(), name);
+        assert!(information.thumbnail.is_none(), "{name}: {:?}", information.thumbnail);
+    }
+    Ok(())
+}
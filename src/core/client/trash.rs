@@ -7,34 +7,34 @@ use wlist_native::common::data::trashes::options::{ListTrashOptions, TrashesOrde
 use wlist_native::core::client::trash::{trash_delete, trash_delete_all, trash_get, trash_list, trash_refresh, trash_restore, trash_trash};
 use wlist_native::core::client::upload::upload_mkdir;
 
-use crate::core::{c, InitializeGuard};
+use crate::core::{api, InitializeGuard};
 
 pub async fn test_none(guard: &InitializeGuard) -> anyhow::Result<()> {
     let root = FileLocation { storage: 0, file_id: 0, is_directory: true, };
 
-    let result = trash_list(c!(guard), 0, ListTrashOptions {
+    let result = api!(trash_list(guard, 0, ListTrashOptions {
         filter: FilesFilter::Both, orders: Default::default(), offset: 0, limit: 1,
-    }).await;
+    }));
     crate::assert_error::<_, wlist_native::common::exceptions::StorageNotFoundError>(result)?;
 
-    let result = trash_refresh(c!(guard), 0).await;
+    let result = api!(trash_refresh(guard, 0));
     crate::assert_error::<_, wlist_native::common::exceptions::StorageNotFoundError>(result)?;
 
-    let result = trash_get(c!(guard), root, false).await;
+    let result = api!(trash_get(guard, root, false));
     crate::assert_error::<_, wlist_native::common::exceptions::StorageNotFoundError>(result)?;
-    let result = trash_get(c!(guard), root, true).await;
+    let result = api!(trash_get(guard, root, true));
     crate::assert_error::<_, wlist_native::common::exceptions::StorageNotFoundError>(result)?;
 
-    let result = trash_trash(c!(guard), root).await;
+    let result = api!(trash_trash(guard, root));
     crate::assert_error::<_, wlist_native::common::exceptions::StorageNotFoundError>(result)?;
 
-    let result = trash_restore(c!(guard), root, 0).await;
+    let result = api!(trash_restore(guard, root, 0));
     crate::assert_error::<_, wlist_native::common::exceptions::StorageNotFoundError>(result)?;
 
-    let result = trash_delete(c!(guard), root).await;
+    let result = api!(trash_delete(guard, root));
     crate::assert_error::<_, wlist_native::common::exceptions::StorageNotFoundError>(result)?;
 
-    let result = trash_delete_all(c!(guard), 0).await;
+    let result = api!(trash_delete_all(guard, 0));
     crate::assert_error::<_, wlist_native::common::exceptions::StorageNotFoundError>(result)?;
 
     Ok(())
@@ -42,51 +42,51 @@ pub async fn test_none(guard: &InitializeGuard) -> anyhow::Result<()> {
 
 pub async fn test_normal(guard: &InitializeGuard, root: FileLocation) -> anyhow::Result<()> {
     // test_list_empty
-    let list = trash_list(c!(guard), root.storage, ListTrashOptions {
+    let list = api!(trash_list(guard, root.storage, ListTrashOptions {
         filter: FilesFilter::Both, orders: Default::default(), offset: 0, limit: 1,
-    }).await?.unwrap_left(); // this is tested after refresh, so needn't refresh.
+    }))?.unwrap_left(); // this is tested after refresh, so needn't refresh.
     assert_eq!(list.total_file, 0);
     assert_eq!(list.total_directory, 0);
     assert_eq!(list.files.len(), 0);
 
     let restore = super::upload::upload(guard, root, "ToRestore.txt".to_string(), Bytes::from_static(b"to restore."), Duplicate::Error).await?;
     let delete = super::upload::upload(guard, root, "ToDelete.txt".to_string(), Bytes::from_static(b"to delete."), Duplicate::Error).await?;
-    let directory = upload_mkdir(c!(guard), root, "ToDirectory".to_string(), Duplicate::Error).await?;
+    let directory = api!(upload_mkdir(guard, root, "ToDirectory".to_string(), Duplicate::Error))?;
 
     // test_trash
-    let restore_trash = trash_trash(c!(guard), restore.get_location(root.storage)).await?;
+    let restore_trash = api!(trash_trash(guard, restore.get_location(root.storage)))?;
     assert_eq!(restore_trash.id, restore.id);
     assert_eq!(restore_trash.is_directory, false);
     assert_eq!(restore_trash.size, Some(11));
     assert!(restore_trash.trash_time.is_some());
-    let delete_trash = trash_trash(c!(guard), delete.get_location(root.storage)).await?;
+    let delete_trash = api!(trash_trash(guard, delete.get_location(root.storage)))?;
     assert_eq!(delete_trash.id, delete.id);
     assert_eq!(delete_trash.is_directory, false);
     assert_eq!(delete_trash.size, Some(10));
     assert!(delete_trash.trash_time.is_some());
-    let directory_trash = trash_trash(c!(guard), directory.get_location(root.storage)).await?;
+    let directory_trash = api!(trash_trash(guard, directory.get_location(root.storage)))?;
     assert_eq!(directory_trash.id, directory.id);
     assert_eq!(directory_trash.is_directory, true);
     assert_eq!(directory_trash.size, Some(0));
     assert!(directory_trash.trash_time.is_some());
 
     // test_restore
-    let result = trash_restore(c!(guard), restore_trash.get_location(root.storage), root.file_id).await;
+    let result = api!(trash_restore(guard, restore_trash.get_location(root.storage), root.file_id));
     if let Some(restore) = crate::may_error::<_, wlist_native::common::exceptions::ComplexOperationError>(result)? {
         assert_eq!(restore.id, restore_trash.id);
         assert_eq!(restore.is_directory, false);
         assert_eq!(restore.parent_id, root.file_id);
         assert_eq!(restore.size, Some(11));
-        trash_trash(c!(guard), restore.get_location(root.storage)).await?;
+        api!(trash_trash(guard, restore.get_location(root.storage)))?;
     }
 
     // test_delete
-    trash_delete(c!(guard), delete_trash.get_location(root.storage)).await?;
+    api!(trash_delete(guard, delete_trash.get_location(root.storage)))?;
 
     // test_list
-    let list = trash_list(c!(guard), root.storage, ListTrashOptions {
+    let list = api!(trash_list(guard, root.storage, ListTrashOptions {
         filter: FilesFilter::Both, orders: IndexMap::from([(TrashesOrder::Directory, Direction::ASCEND)]), offset: 0, limit: 3,
-    }).await?.unwrap_left();
+    }))?.unwrap_left();
     assert_eq!(list.total_file, 1);
     assert_eq!(list.total_directory, 1);
     assert_eq!(list.files.len(), 2);
@@ -94,11 +94,11 @@ pub async fn test_normal(guard: &InitializeGuard, root: FileLocation) -> anyhow:
     assert_eq!(list.files[1].name.as_str(), "ToRestore.txt");
 
     // test_delete_all
-    let result = trash_delete_all(c!(guard), root.storage).await;
+    let result = api!(trash_delete_all(guard, root.storage));
     if let Some(()) = crate::may_error::<_, wlist_native::common::exceptions::ComplexOperationError>(result)? {
-        let list = trash_list(c!(guard), root.storage, ListTrashOptions {
+        let list = api!(trash_list(guard, root.storage, ListTrashOptions {
             filter: FilesFilter::Both, orders: Default::default(), offset: 0, limit: 1,
-        }).await?.unwrap_left();
+        }))?.unwrap_left();
         assert_eq!(list.total_file, 0);
         assert_eq!(list.total_directory, 0);
         assert_eq!(list.files.len(), 0);
@@ -5,7 +5,7 @@ use anyhow::Error;
 use bytes::{Buf, BufMut, Bytes, BytesMut};
 use rand::Rng;
 use tokio::sync::watch::channel;
-use tokio::task::{yield_now, JoinSet};
+use tokio::task::yield_now;
 use tokio::time::sleep;
 use tracing::{debug, warn};
 
@@ -17,30 +17,29 @@ use wlist_native::core::client::trash::{trash_delete, trash_trash};
 use wlist_native::core::client::upload::{upload_cancel, upload_confirm, upload_extra_md5s, upload_finish, upload_mkdir, upload_request, upload_stream};
 use wlist_native::core::helper::hasher::Md5Hasher;
 
-use crate::core::{c, InitializeGuard};
+use crate::core::{api, scoped, InitializeGuard};
 
 pub async fn test_none(guard: &InitializeGuard) -> anyhow::Result<()> {
     let root = FileLocation { storage: 0, file_id: 0, is_directory: true, };
     let md5 = Md5Hasher::new().finalize().await;
 
     // test_incorrect_storage
-    let result = upload_mkdir(c!(guard), root, "directory".to_string(), Duplicate::Error).await;
+    let result = api!(upload_mkdir(guard, root, "directory".to_string(), Duplicate::Error));
     crate::assert_error::<_, wlist_native::common::exceptions::StorageNotFoundError>(result)?;
-    let result = upload_request(c!(guard), root, "hello.txt".to_string(), 5, md5.clone(), Some(vec![md5.clone()]), Duplicate::Error).await;
+    let result = api!(upload_request(guard, root, "hello.txt".to_string(), 5, md5.clone(), Some(vec![md5.clone()]), Duplicate::Error));
     crate::assert_error::<_, wlist_native::common::exceptions::StorageNotFoundError>(result)?;
     Ok(())
 }
 
-pub async fn upload(guard: &InitializeGuard, parent: FileLocation, name: String, data: Bytes, duplicate: Duplicate) -> anyhow::Result<FileInformation> {
+pub async fn hash(data: &Bytes, chunk: Option<usize>) -> (String, Option<Vec<String>>) {
     let len = data.remaining();
-    let (md5, md5s) = match upload_extra_md5s(c!(guard), parent.storage).await? {
+    match chunk {
         None => {
             let md5 = Md5Hasher::new();
             md5.update(data.clone()).await;
             (md5.finalize().await, None)
         },
         Some(chunk) => {
-            let chunk = chunk.get();
             let md5 = Md5Hasher::new();
             let mut md5s = Vec::new();
             let mut i = 0;
@@ -64,32 +63,38 @@ pub async fn upload(guard: &InitializeGuard, parent: FileLocation, name: String,
             if md5s.is_empty() { md5s.push(md5.clone()); }
             (md5, Some(md5s))
         },
-    };
-    let confirmation = upload_request(c!(guard), parent, name, len as u64, md5, md5s, duplicate).await?;
+    }
+}
+
+pub async fn upload(guard: &InitializeGuard, parent: FileLocation, name: String, data: Bytes, duplicate: Duplicate) -> anyhow::Result<FileInformation> {
+    let len = data.remaining();
+    let chunk = api!(upload_extra_md5s(guard, parent.storage))?.map(|chunk| chunk.get());
+    let (md5, md5s) = hash(&data, chunk).await;
+    let confirmation = api!(upload_request(guard, parent, name, len as u64, md5, md5s, duplicate))?;
     if !confirmation.done {
-        let information = upload_confirm(c!(guard), confirmation.token.clone()).await?;
-        let mut set = JoinSet::new();
-        for (chunk, id) in information.chunks.into_iter().zip(0..) {
+        let information = api!(upload_confirm(guard, confirmation.token.clone()))?;
+        let tracker = &crate::Tracker::new(&confirmation.token);
+        scoped(information.chunks.into_iter().zip(0..).map(|(chunk, id)| {
             let l = chunk.start as usize;
             let r = l + chunk.size as usize;
             let data = data.slice(l..r);
-            let guard = unsafe { &*(guard as *const InitializeGuard) }; // Safety: not cancelled.
             let token = confirmation.token.clone();
-            set.spawn(async move {
+            async move {
                 const CHUNK: usize = 1 << 10;
                 let mut i = 0;
                 loop {
                     let l = i * CHUNK;
-                    let r = min((i + 1) * CHUNK, len);
+                    let r = min((i + 1) * CHUNK, data.len());
                     if l >= r { break; }
                     let mut chunk = data.slice(l..r); // slice to test upload in chunk
                     let (tx, mut rx) = channel(0);
                     tokio::select! {
-                        r = async { upload_stream(c!(guard), token.clone(), id, &mut chunk, tx, channel(true).1).await } => r?,
+                        r = async { api!(upload_stream(guard, token.clone(), id, &mut chunk, tx, channel(true).1)) } => r?,
                         _ = async { loop {
                             if rx.changed().await.is_ok() {
                                 let transferred_bytes = *rx.borrow_and_update();
                                 debug!(%transferred_bytes, "Uploading.");
+                                tracker.update(format_args!("chunk {id}: {transferred_bytes} bytes of piece {i}"));
                                 sleep(Duration::from_millis(50)).await;
                             }
                             yield_now().await
@@ -98,11 +103,10 @@ pub async fn upload(guard: &InitializeGuard, parent: FileLocation, name: String,
                     i += 1;
                 }
                 Ok::<_, Error>(())
-            });
-        }
-        for r in set.join_all().await { r?; }
+            }
+        })).await?;
     }
-    let information = upload_finish(c!(guard), confirmation.token).await?;
+    let information = api!(upload_finish(guard, confirmation.token))?;
     assert_eq!(information.is_directory, false);
     assert_eq!(information.parent_id, parent.file_id);
     assert_eq!(information.size, Some(len as u64));
@@ -112,28 +116,28 @@ pub async fn upload(guard: &InitializeGuard, parent: FileLocation, name: String,
 }
 
 async fn mkdir_and_delete(guard: &InitializeGuard, root: FileLocation, name: String, duplicate: Duplicate) -> anyhow::Result<()> {
-    let file = upload_mkdir(c!(guard), root, name, duplicate).await?;
+    let file = api!(upload_mkdir(guard, root, name, duplicate))?;
     assert_eq!(file.is_directory, true);
     assert_eq!(file.size, Some(0));
     let list = super::list::list(guard, file.get_location(root.storage), None).await?;
     assert_eq!(list.total_file, 0);
     assert_eq!(list.total_directory, 0);
-    let information = trash_trash(c!(guard), file.get_location(root.storage)).await?;
-    trash_delete(c!(guard), information.get_location(root.storage)).await
+    let information = api!(trash_trash(guard, file.get_location(root.storage)))?;
+    api!(trash_delete(guard, information.get_location(root.storage)))
 }
 
 async fn upload_and_delete(guard: &InitializeGuard, root: FileLocation, name: String, data: Bytes, duplicate: Duplicate) -> anyhow::Result<()> {
     let file = upload(guard, root, name, data.clone(), duplicate).await?;
-    let confirmation = download_request(c!(guard), file.get_location(root.storage), 0, u64::MAX).await?;
+    let confirmation = api!(download_request(guard, file.get_location(root.storage), 0, u64::MAX))?;
     assert_eq!(confirmation.size, data.remaining() as u64);
     let (downloaded, from, to) = super::download::download0(guard, &confirmation.token).await?;
     assert_eq!(from, 0); assert_eq!(to, data.remaining() as u64);
     assert_eq!(data, downloaded, "data != downloaded");
-    let information = trash_trash(c!(guard), file.get_location(root.storage)).await?;
-    trash_delete(c!(guard), information.get_location(root.storage)).await
+    let information = api!(trash_trash(guard, file.get_location(root.storage)))?;
+    api!(trash_delete(guard, information.get_location(root.storage)))
 }
 
-fn generate_md5() -> String {
+pub fn generate_md5() -> String {
     const ALL: &str = "0123456789abcdef";
     let mut key = Vec::with_capacity(32);
     let mut rand = rand::thread_rng();
@@ -168,14 +172,14 @@ pub async fn test_normal(guard: &InitializeGuard, root: FileLocation) -> anyhow:
 
     // upload_test_cancel
     let md5 = generate_md5();
-    let confirmation = upload_request(c!(guard), root, "hello.txt".to_string(), 5, md5.clone(), Some(vec![md5.clone()]), Duplicate::Error).await?;
+    let confirmation = api!(upload_request(guard, root, "hello.txt".to_string(), 5, md5.clone(), Some(vec![md5.clone()]), Duplicate::Error))?;
     if confirmation.done {
         warn!(%md5, "upload_test_cancel: uploaded done.");
-        let information = upload_finish(c!(guard), confirmation.token).await?;
-        let information = trash_trash(c!(guard), information.get_location(root.storage)).await?;
-        trash_delete(c!(guard), information.get_location(root.storage)).await?;
+        let information = api!(upload_finish(guard, confirmation.token))?;
+        let information = api!(trash_trash(guard, information.get_location(root.storage)))?;
+        api!(trash_delete(guard, information.get_location(root.storage)))?;
     } else {
-        upload_cancel(c!(guard), confirmation.token).await?;
+        api!(upload_cancel(guard, confirmation.token))?;
     }
 
     // TODO: test duplicate
@@ -184,35 +188,75 @@ pub async fn test_normal(guard: &InitializeGuard, root: FileLocation) -> anyhow:
     Ok(())
 }
 
+pub async fn test_instant(guard: &InitializeGuard, root: FileLocation) -> anyhow::Result<()> {
+    let mut bytes = BytesMut::new();
+    let mut rand = rand::thread_rng();
+    for _ in 0..rand.gen_range(128..4<<10) {
+        bytes.put_u8(rand.gen());
+    }
+    let data = bytes.freeze();
+    let len = data.remaining() as u64;
+    let origin = upload(guard, root, "InstantOrigin.txt".to_string(), data.clone(), Duplicate::Error).await?;
+    let chunk = api!(upload_extra_md5s(guard, root.storage))?.map(|chunk| chunk.get());
+    let (md5, md5s) = hash(&data, chunk).await;
+
+    // instant_test_hit
+    let confirmation = api!(upload_request(guard, root, "InstantHit.txt".to_string(), len, md5.clone(), md5s.clone(), Duplicate::Error))?;
+    assert_eq!(confirmation.done, true);
+    let information = api!(upload_finish(guard, confirmation.token))?;
+    assert_ne!(information.id, origin.id);
+    assert_eq!(information.name.as_str(), "InstantHit.txt");
+    assert_eq!(information.parent_id, root.file_id);
+    assert_eq!(information.size, Some(len));
+    let confirmation = api!(download_request(guard, information.get_location(root.storage), 0, u64::MAX))?;
+    assert_eq!(confirmation.size, len);
+    let (downloaded, _, _) = super::download::download0(guard, &confirmation.token).await?;
+    assert_eq!(data, downloaded, "data != downloaded");
+    let information = api!(trash_trash(guard, information.get_location(root.storage)))?;
+    api!(trash_delete(guard, information.get_location(root.storage)))?;
+
+    // instant_test_size_mismatched
+    for size in [len - 1, len + 1] {
+        let result = api!(upload_request(guard, root, "InstantMiss.txt".to_string(), size, md5.clone(), md5s.clone(), Duplicate::Error));
+        if let Some(confirmation) = crate::may_error::<_, wlist_native::common::exceptions::IncorrectArgumentError>(result)? {
+            assert_eq!(confirmation.done, false, "size {size} short-circuited with md5 of {len} bytes");
+            api!(upload_cancel(guard, confirmation.token))?;
+        }
+    }
+
+    let information = api!(trash_trash(guard, origin.get_location(root.storage)))?;
+    api!(trash_delete(guard, information.get_location(root.storage)))
+}
+
 pub async fn test_empty(guard: &InitializeGuard, root: FileLocation) -> anyhow::Result<()> {
     let md5 = Md5Hasher::new().finalize().await;
 
     // test_incorrect_parent
     let file = FileLocation { storage: 0, file_id: 0, is_directory: false, };
-    let result = upload_mkdir(c!(guard), file, "directory".to_string(), Duplicate::Error).await;
+    let result = api!(upload_mkdir(guard, file, "directory".to_string(), Duplicate::Error));
     crate::assert_error::<_, wlist_native::common::exceptions::IncorrectArgumentError>(result)?;
-    let result = upload_request(c!(guard), file, "chunk.txt".to_string(), 5, md5.clone(), Some(vec![md5.clone()]), Duplicate::Error).await;
+    let result = api!(upload_request(guard, file, "chunk.txt".to_string(), 5, md5.clone(), Some(vec![md5.clone()]), Duplicate::Error));
     crate::assert_error::<_, wlist_native::common::exceptions::IncorrectArgumentError>(result)?;
 
     // test_incorrect_name
-    let result = upload_mkdir(c!(guard), root, "".to_string(), Duplicate::Error).await;
+    let result = api!(upload_mkdir(guard, root, "".to_string(), Duplicate::Error));
     crate::assert_error::<_, wlist_native::common::exceptions::IncorrectArgumentError>(result)?;
-    let result = upload_request(c!(guard), root, "".to_string(), 5, md5.clone(), Some(vec![md5.clone()]), Duplicate::Error).await;
+    let result = api!(upload_request(guard, root, "".to_string(), 5, md5.clone(), Some(vec![md5.clone()]), Duplicate::Error));
     crate::assert_error::<_, wlist_native::common::exceptions::IncorrectArgumentError>(result)?;
-    let result = upload_mkdir(c!(guard), root, "a".repeat(32768), Duplicate::Error).await;
+    let result = api!(upload_mkdir(guard, root, "a".repeat(32768), Duplicate::Error));
     crate::assert_error::<_, wlist_native::common::exceptions::IncorrectArgumentError>(result)?;
-    let result = upload_request(c!(guard), root, "a".repeat(32768), 5, md5.clone(), Some(vec![md5.clone()]), Duplicate::Error).await;
+    let result = api!(upload_request(guard, root, "a".repeat(32768), 5, md5.clone(), Some(vec![md5.clone()]), Duplicate::Error));
     crate::assert_error::<_, wlist_native::common::exceptions::IncorrectArgumentError>(result)?;
 
     // test_incorrect_md5
     for invalid_md5 in ["".to_string(), "A".to_string(), "a".repeat(30) + "0A", "A".repeat(32), "-".repeat(32)] {
-        let result = upload_request(c!(guard), root, "hello.txt".to_string(), 5, invalid_md5.clone(), Some(vec![invalid_md5.clone()]), Duplicate::Error).await;
+        let result = api!(upload_request(guard, root, "hello.txt".to_string(), 5, invalid_md5.clone(), Some(vec![invalid_md5.clone()]), Duplicate::Error));
         crate::assert_error::<_, wlist_native::common::exceptions::IncorrectArgumentError>(result)?;
-        let result = upload_request(c!(guard), root, "hello.txt".to_string(), 5, invalid_md5.clone(), Some(vec![md5.clone()]), Duplicate::Error).await;
+        let result = api!(upload_request(guard, root, "hello.txt".to_string(), 5, invalid_md5.clone(), Some(vec![md5.clone()]), Duplicate::Error));
         crate::assert_error::<_, wlist_native::common::exceptions::IncorrectArgumentError>(result)?;
-        let result = upload_request(c!(guard), root, "hello.txt".to_string(), 5, md5.clone(), Some(vec![md5.clone(); 2]), Duplicate::Error).await;
+        let result = api!(upload_request(guard, root, "hello.txt".to_string(), 5, md5.clone(), Some(vec![md5.clone(); 2]), Duplicate::Error));
         crate::assert_error::<_, wlist_native::common::exceptions::IncorrectArgumentError>(result)?;
-        let result = upload_request(c!(guard), root, "hello.txt".to_string(), 5, md5.clone(), Some(vec![]), Duplicate::Error).await;
+        let result = api!(upload_request(guard, root, "hello.txt".to_string(), 5, md5.clone(), Some(vec![]), Duplicate::Error));
         crate::assert_error::<_, wlist_native::common::exceptions::IncorrectArgumentError>(result)?;
     }
     Ok(())
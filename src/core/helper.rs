@@ -1,51 +1,161 @@
 #[tokio::test]
-async fn read_buffer() {
-    let data = vec![1, 2, 3];
-    let buffer = unsafe { wlist_native::core::helper::buffer::new_read_buffer(data.as_ptr(), 3) };
-    let mut iter = buffer.into_iter();
-    assert_eq!(iter.next(), Some(1));
-    assert_eq!(iter.next(), Some(2));
-    assert_eq!(iter.next(), Some(3));
-    assert_eq!(iter.next(), None);
+async fn md5() -> anyhow::Result<()> {
+    crate::test("md5".to_string(), crate::test_timeout(), async {
+        let md5 = wlist_native::core::helper::hasher::Md5Hasher::new();
+        md5.update(bytes::Bytes::from_static("hello world".as_bytes())).await;
+        assert_eq!(&md5.finalize().await, "5eb63bbbe01eeed093cb22bb8f5acdc3");
+        Ok(())
+    }).await
 }
 
 #[tokio::test]
-async fn write_buffer() {
-    use bytes::BufMut;
-    let mut data = vec![0; 3];
-    let mut buffer = unsafe { wlist_native::core::helper::buffer::new_write_buffer(data.as_mut_ptr(), 3) };
-    buffer.put_u8(1);
-    buffer.put_u8(2);
-    buffer.put_u8(3);
-    drop(buffer);
-    assert_eq!(&data, &[1, 2, 3]);
+async fn sha256() -> anyhow::Result<()> {
+    crate::test("sha256".to_string(), crate::test_timeout(), async {
+        let sha256 = wlist_native::core::helper::hasher::Sha256Hasher::new();
+        sha256.update(bytes::Bytes::from_static("hello world".as_bytes())).await;
+        assert_eq!(&sha256.finalize().await, "b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9");
+        Ok(())
+    }).await
+}
+
+/// Random pieces of up to 64 KiB, every fourth one empty.
+fn pieces(seed: u64, count: usize) -> Vec<bytes::Bytes> {
+    use rand::{Rng, SeedableRng};
+    let mut rng = rand::rngs::StdRng::seed_from_u64(seed);
+    (0..count).map(|i| {
+        let mut piece = vec![0; if i % 4 == 0 { 0 } else { rng.gen_range(0..=64 << 10) }];
+        rng.fill(&mut piece[..]);
+        bytes::Bytes::from(piece)
+    }).collect()
+}
+
+fn hex(digest: &[u8]) -> String {
+    digest.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+#[tokio::test]
+async fn md5_streaming() -> anyhow::Result<()> {
+    crate::test("md5_streaming".to_string(), crate::test_timeout(), async {
+        use md5::Digest;
+        let pieces = pieces(0, 256);
+        let md5 = wlist_native::core::helper::hasher::Md5Hasher::new();
+        let mut reference = md5::Md5::new();
+        for piece in pieces {
+            reference.update(&piece);
+            md5.update(piece).await;
+        }
+        assert_eq!(md5.finalize().await, hex(&reference.finalize()));
+        Ok(())
+    }).await
 }
 
 #[tokio::test]
-#[should_panic]
-async fn write_buffer_panic() {
-    use bytes::BufMut;
-    let mut data = vec![0; 3];
-    let mut buffer = unsafe { wlist_native::core::helper::buffer::new_write_buffer(data.as_mut_ptr(), 0) };
-    buffer.put_u8(1);
+async fn sha256_streaming() -> anyhow::Result<()> {
+    crate::test("sha256_streaming".to_string(), crate::test_timeout(), async {
+        use sha2::Digest;
+        let pieces = pieces(1, 256);
+        let sha256 = wlist_native::core::helper::hasher::Sha256Hasher::new();
+        let mut reference = sha2::Sha256::new();
+        for piece in pieces {
+            reference.update(&piece);
+            sha256.update(piece).await;
+        }
+        assert_eq!(sha256.finalize().await, hex(&reference.finalize()));
+        Ok(())
+    }).await
 }
 
 #[tokio::test]
-async fn md5() {
-    let md5 = wlist_native::core::helper::hasher::Md5Hasher::new();
-    md5.update(bytes::Bytes::from_static("hello world".as_bytes())).await;
-    assert_eq!(&md5.finalize().await, "5eb63bbbe01eeed093cb22bb8f5acdc3");
+async fn hasher_empty() -> anyhow::Result<()> {
+    crate::test("hasher_empty".to_string(), crate::test_timeout(), async {
+        let md5 = wlist_native::core::helper::hasher::Md5Hasher::new();
+        assert_eq!(&md5.finalize().await, "d41d8cd98f00b204e9800998ecf8427e");
+        let sha256 = wlist_native::core::helper::hasher::Sha256Hasher::new();
+        assert_eq!(&sha256.finalize().await, "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855");
+        Ok(())
+    }).await
 }
 
+/// Updates started in order but awaited together must be applied in call order.
 #[tokio::test]
-async fn sha256() {
-    let sha256 = wlist_native::core::helper::hasher::Sha256Hasher::new();
-    sha256.update(bytes::Bytes::from_static("hello world".as_bytes())).await;
-    assert_eq!(&sha256.finalize().await, "b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9");
+async fn hasher_order() -> anyhow::Result<()> {
+    crate::test("hasher_order".to_string(), crate::test_timeout(), async {
+        use md5::Digest;
+        let pieces = pieces(2, 64);
+        let md5 = wlist_native::core::helper::hasher::Md5Hasher::new();
+        futures::future::join_all(pieces.iter().map(|piece| md5.update(piece.clone()))).await;
+        let mut reference = md5::Md5::new();
+        pieces.iter().for_each(|piece| reference.update(piece));
+        assert_eq!(md5.finalize().await, hex(&reference.finalize()), "updates are reordered");
+        Ok(())
+    }).await
+}
+
+/// Updates from many tasks must neither be lost nor applied twice.
+/// Every task feeds the same piece, so the digest does not depend on interleaving.
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn hasher_concurrent() -> anyhow::Result<()> {
+    crate::test("hasher_concurrent".to_string(), crate::test_timeout(), async {
+        use md5::Digest;
+        const TASKS: usize = 16;
+        const UPDATES: usize = 64;
+        let piece = bytes::Bytes::from_static(b"@wlist concurrent hasher piece\n");
+        let md5 = std::sync::Arc::new(wlist_native::core::helper::hasher::Md5Hasher::new());
+        let mut set = tokio::task::JoinSet::new();
+        for _ in 0..TASKS {
+            let md5 = std::sync::Arc::clone(&md5);
+            let piece = piece.clone();
+            set.spawn(async move {
+                for _ in 0..UPDATES { md5.update(piece.clone()).await; }
+            });
+        }
+        set.join_all().await;
+        let md5 = std::sync::Arc::try_unwrap(md5).map_err(|_| anyhow::anyhow!("hasher is still shared"))?;
+        assert_eq!(md5.finalize().await, hex(&md5::Md5::digest(piece.repeat(TASKS * UPDATES))));
+        Ok(())
+    }).await
+}
+
+/// Hashes 512 MiB in 1 MiB updates and logs the throughput.
+/// Run with `cargo test --release hasher_large -- --ignored --nocapture`.
+#[tokio::test]
+#[ignore]
+async fn hasher_large() -> anyhow::Result<()> {
+    crate::test("hasher_large".to_string(), crate::test_timeout(), async {
+        use sha2::Digest;
+        let guard = crate::initialize(true).await?; // for the tracing subscriber
+        const SIZE: usize = 512 << 20;
+        const PIECE: usize = 1 << 20;
+        let mut piece = vec![0; PIECE];
+        rand::Rng::fill(&mut rand::thread_rng(), &mut piece[..]);
+        let piece = bytes::Bytes::from(piece);
+
+        let md5 = wlist_native::core::helper::hasher::Md5Hasher::new();
+        let sha256 = wlist_native::core::helper::hasher::Sha256Hasher::new();
+        let start = std::time::Instant::now();
+        for _ in 0..SIZE / PIECE {
+            tokio::join!(md5.update(piece.clone()), sha256.update(piece.clone()));
+        }
+        let (md5, sha256) = tokio::join!(md5.finalize(), sha256.finalize());
+        let elapsed = start.elapsed();
+        tracing::info!(mebibytes = SIZE >> 20, ?elapsed, throughput = (SIZE >> 20) as f64 / elapsed.as_secs_f64(), "Hashed with md5 and sha256.");
+
+        let mut md5_reference = md5::Md5::new();
+        let mut sha256_reference = sha2::Sha256::new();
+        for _ in 0..SIZE / PIECE {
+            md5_reference.update(&piece);
+            sha256_reference.update(&piece);
+        }
+        assert_eq!(md5, hex(&md5_reference.finalize()));
+        assert_eq!(sha256, hex(&sha256_reference.finalize()));
+        crate::uninitialize(guard)
+    }).await
 }
 
 #[tokio::test]
 async fn initialize() -> anyhow::Result<()> {
-    let guard = super::initialize(false).await?;
-    super::uninitialize(guard).await
+    crate::test("initialize".to_string(), crate::test_timeout(), async {
+        let guard = super::initialize(false).await?;
+        super::uninitialize(guard).await
+    }).await
 }
@@ -7,6 +7,23 @@ macro_rules! c {
 }
 use c;
 
+/// Calls a client api like `api!(files_get(guard, location, false, false))`,
+/// awaiting it and recording the call in the event log of the current test.
+/// `api!(@client files_get(client, ...))` does the same on a client of its own, such as a worker's.
+/// Logins are not recorded, since their arguments carry the password.
+macro_rules! api {
+    (@client $($f: ident)::+ ($client: expr $(, $a: expr)* $(,)?)) => {{
+        let arguments = ($($a,)*);
+        let values = $crate::events::Arguments::describe(&arguments);
+        $crate::events::record(stringify!($($f)::+), &[$(stringify!($a)),*], values,
+            $crate::events::Apply::apply($($f)::+, &mut *$client, arguments)).await
+    }};
+    ($($f: ident)::+ ($g: ident $(, $a: expr)* $(,)?)) => {
+        $crate::core::api!(@client $($f)::+ ($crate::core::c!($g) $(, $a)*))
+    };
+}
+use api;
+
 struct InitializeGuard {
     parent: crate::InitializeGuard,
     password: &'static str,
@@ -30,6 +47,15 @@ async fn initialize(unique: bool) -> anyhow::Result<InitializeGuard> {
     Ok(InitializeGuard { parent: guard, password })
 }
 
+use crate::scoped;
+
+/// Waits for the other tasks, failing instead of hanging if one of them failed before reaching `barrier`.
+async fn rendezvous(barrier: &tokio::sync::Barrier) -> anyhow::Result<()> {
+    let timeout = crate::timeout_from_env("WLIST_TEST_BARRIER_TIMEOUT", std::time::Duration::from_secs(60));
+    tokio::time::timeout(timeout, barrier.wait()).await
+        .map(drop).map_err(|_| anyhow::anyhow!("other tasks did not reach the barrier within {timeout:?}"))
+}
+
 #[inline]
 async fn uninitialize(guard: InitializeGuard) -> anyhow::Result<()> {
     crate::uninitialize(guard.parent)
@@ -0,0 +1,161 @@
+use std::collections::BTreeMap;
+use std::fmt::{Debug, Write as _};
+use std::fs::{File, OpenOptions};
+use std::future::Future;
+use std::io::{BufWriter, Write as _};
+use std::sync::Mutex;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+const OUTPUT: &str = "run/events";
+/// Longest recorded form of a single argument, so streamed buffers are not dumped whole.
+const ARGUMENT_LIMIT: usize = 256;
+
+/// The event log of `test`.
+pub fn path(test: &str) -> String {
+    let test = test.replace(|c: char| !c.is_ascii_alphanumeric() && c != '_', "_");
+    format!("{OUTPUT}/{test}.jsonl")
+}
+
+/// The event log of each running test, buffered until the test ends.
+static WRITERS: Mutex<BTreeMap<String, BufWriter<File>>> = Mutex::new(BTreeMap::new());
+
+/// Flushes and closes the event log of a test when dropped, even if the test panics.
+pub struct Log(String);
+
+impl Drop for Log {
+    fn drop(&mut self) {
+        if let Some(mut writer) = WRITERS.lock().unwrap().remove(&self.0) {
+            let _ = writer.flush();
+        }
+    }
+}
+
+/// Truncates the event log of `test`, so it only holds the current run, and keeps it open until the [Log] is dropped.
+pub fn start(test: &str) -> Log {
+    let _ = std::fs::create_dir_all(OUTPUT);
+    if let Ok(file) = File::create(path(test)) {
+        WRITERS.lock().unwrap().insert(test.to_string(), BufWriter::new(file));
+    }
+    Log(test.to_string())
+}
+
+/// Calls a client api function with the client and a tuple of its remaining arguments.
+pub trait Apply<C, A> {
+    type Output;
+    fn apply(self, client: C, arguments: A) -> Self::Output;
+}
+
+/// Describes each argument of a tuple.
+pub trait Arguments {
+    fn describe(&self) -> Vec<String>;
+}
+
+macro_rules! apply {
+    ($($a: ident),*) => {
+        impl<F: FnOnce(C, $($a),*) -> R, C, R, $($a),*> Apply<C, ($($a,)*)> for F {
+            type Output = R;
+            #[allow(non_snake_case)]
+            fn apply(self, client: C, ($($a,)*): ($($a,)*)) -> R {
+                self(client, $($a),*)
+            }
+        }
+
+        impl<$($a: Debug),*> Arguments for ($($a,)*) {
+            #[allow(non_snake_case)]
+            fn describe(&self) -> Vec<String> {
+                let ($($a,)*) = self;
+                vec![$(describe($a)),*]
+            }
+        }
+    };
+}
+apply!();
+apply!(A);
+apply!(A, B);
+apply!(A, B, D);
+apply!(A, B, D, E);
+apply!(A, B, D, E, G);
+apply!(A, B, D, E, G, H);
+apply!(A, B, D, E, G, H, I);
+apply!(A, B, D, E, G, H, I, J);
+
+/// Stops formatting once [ARGUMENT_LIMIT] bytes are written.
+struct Limited(String);
+
+impl std::fmt::Write for Limited {
+    fn write_str(&mut self, s: &str) -> std::fmt::Result {
+        let room = ARGUMENT_LIMIT.saturating_sub(self.0.len());
+        if s.len() <= room {
+            self.0.push_str(s);
+            return Ok(());
+        }
+        let mut end = room;
+        while !s.is_char_boundary(end) { end -= 1; }
+        self.0.push_str(&s[..end]);
+        self.0.push_str("...");
+        Err(std::fmt::Error)
+    }
+}
+
+fn describe(value: &impl Debug) -> String {
+    let mut limited = Limited(String::new());
+    let _ = write!(limited, "{value:?}");
+    limited.0
+}
+
+fn json(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => { let _ = write!(out, "\\u{:04x}", c as u32); },
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// Finds the storage id in an argument named like `storage`, or in the `storage: ` part of a `FileLocation`.
+fn storage(names: &[&str], values: &[String]) -> Option<String> {
+    names.iter().zip(values).find_map(|(name, value)| {
+        let value = if name.ends_with("storage") { value.as_str() } else { value.split_once("storage: ")?.1 };
+        let digits = value.chars().take_while(char::is_ascii_digit).collect::<String>();
+        (!digits.is_empty()).then_some(digits)
+    })
+}
+
+/// Awaits `call` and appends it to the event log of the current test.
+pub async fn record<T>(function: &str, names: &[&str], values: Vec<String>, call: impl Future<Output=anyhow::Result<T>>) -> anyhow::Result<T> {
+    let start = Instant::now();
+    let result = call.await;
+    let milliseconds = start.elapsed().as_secs_f64() * 1000.0;
+    let test = crate::current_test();
+    let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis()).unwrap_or(0);
+    let storage = storage(names, &values);
+    let arguments = names.iter().zip(&values).map(|(name, value)| format!("{}:{}", json(name), json(value))).collect::<Vec<_>>().join(",");
+    let (kind, value) = match &result {
+        Ok(_) => ("ok", std::any::type_name::<T>().to_string()),
+        Err(e) => ("error", format!("{e:#}")),
+    };
+    let line = format!(
+        r#"{{"timestamp":{timestamp},"test":{},"call":{},"storage":{},"arguments":{{{arguments}}},"milliseconds":{milliseconds:.3},"result":{},"value":{}}}"#,
+        json(&test), json(function), storage.as_deref().unwrap_or("null"), json(kind), json(&value),
+    );
+    match WRITERS.lock().unwrap().get_mut(&test) {
+        Some(writer) => { let _ = writeln!(writer, "{line}"); },
+        // Not inside [crate::test], so nothing would flush a buffer.
+        None => {
+            let _ = std::fs::create_dir_all(OUTPUT);
+            if let Ok(mut file) = OpenOptions::new().create(true).append(true).open(path(&test)) {
+                let _ = writeln!(file, "{line}");
+            }
+        },
+    }
+    result
+}
@@ -1,4 +1,8 @@
+use std::collections::BTreeMap;
 use std::fmt::{Debug, Display};
+use std::future::Future;
+use std::sync::Mutex;
+use std::time::Duration;
 
 use tokio::sync::{OnceCell, RwLock, RwLockReadGuard, RwLockWriteGuard};
 use tracing::Level;
@@ -6,6 +10,8 @@ use tracing_subscriber::layer::SubscriberExt;
 use tracing_subscriber::util::SubscriberInitExt;
 use tracing_subscriber::Layer;
 
+#[cfg(test)]
+mod events;
 #[cfg(test)]
 mod common;
 #[cfg(test)]
@@ -45,6 +51,102 @@ pub fn uninitialize(guard: InitializeGuard) -> anyhow::Result<()> {
     Ok(())
 }
 
+tokio::task_local! {
+    /// The name of the running test, set by [test].
+    static TEST: String;
+}
+
+/// The name of the running test, falling back to the name of the test thread.
+pub fn current_test() -> String {
+    TEST.try_with(String::clone).unwrap_or_else(|_| std::thread::current().name().unwrap_or("main").to_string())
+}
+
+/// Runs `future` as the test `name`, failing it with a hang report after `duration`.
+pub async fn test<T>(name: String, duration: Duration, future: impl Future<Output=anyhow::Result<T>>) -> anyhow::Result<T> {
+    #[cfg(test)]
+    let _log = events::start(&name);
+    TEST.scope(name.clone(), timeout(&name, duration, future)).await
+}
+
+/// Keeps the name of the current test for `future`, before it is spawned onto a task of its own.
+pub fn in_current_test<F: Future>(future: F) -> impl Future<Output=F::Output> {
+    TEST.scope(current_test(), future)
+}
+
+/// Runs every future concurrently inside the calling task, so they may borrow the guard.
+/// Dropping the returned future, e.g. on a timeout, drops every unfinished one with it.
+pub async fn scoped<T, F: Future<Output=anyhow::Result<T>>>(futures: impl IntoIterator<Item=F>) -> anyhow::Result<Vec<T>> {
+    futures::future::try_join_all(futures).await
+}
+
+/// Outstanding tokens and the last progress of each, keyed by test, dumped when a phase times out.
+static PENDING: Mutex<BTreeMap<(String, String), String>> = Mutex::new(BTreeMap::new());
+
+/// Records the progress of an outstanding token until dropped.
+pub struct Tracker {
+    key: (String, String),
+}
+
+impl Tracker {
+    pub fn new(token: &impl Debug) -> Self {
+        let key = (current_test(), format!("{token:?}"));
+        PENDING.lock().unwrap().insert(key.clone(), "pending".to_string());
+        Self { key }
+    }
+
+    pub fn update(&self, state: impl Debug) {
+        PENDING.lock().unwrap().insert(self.key.clone(), format!("{state:?}"));
+    }
+}
+
+impl Drop for Tracker {
+    fn drop(&mut self) {
+        PENDING.lock().unwrap().remove(&self.key);
+    }
+}
+
+/// Reads a timeout in seconds from `variable`, or falls back to `default`.
+pub fn timeout_from_env(variable: &str, default: Duration) -> Duration {
+    std::env::var(variable).ok().and_then(|s| s.parse().ok()).map(Duration::from_secs).unwrap_or(default)
+}
+
+/// The timeout of a whole test, from `WLIST_TEST_TIMEOUT`.
+pub fn test_timeout() -> Duration {
+    timeout_from_env("WLIST_TEST_TIMEOUT", Duration::from_secs(4 * 60 * 60))
+}
+
+async fn hang_report() -> String {
+    let mut report = String::new();
+    let test = current_test();
+    for ((_, token), state) in PENDING.lock().unwrap().iter().filter(|((t, _), _)| *t == test) {
+        report.push_str(&format!("  outstanding {token}: {state}\n"));
+    }
+    let handle = tokio::runtime::Handle::current();
+    report.push_str(&format!("  alive tasks: {}\n", handle.metrics().num_alive_tasks()));
+    #[cfg(all(tokio_unstable, tokio_taskdump))] {
+        let dump = handle.dump().await;
+        for (i, task) in dump.tasks().iter().enumerate() {
+            report.push_str(&format!("  task {i}:\n{}\n", task.trace()));
+        }
+    }
+    report
+}
+
+/// Fails with a report of outstanding tokens and tasks if `future` runs longer than `duration`.
+/// Build with `RUSTFLAGS="--cfg tokio_unstable --cfg tokio_taskdump"` to include task backtraces.
+pub async fn timeout<T>(phase: &str, duration: Duration, future: impl Future<Output=anyhow::Result<T>>) -> anyhow::Result<T> {
+    let mut future = std::pin::pin!(future);
+    tokio::select! {
+        result = &mut future => result,
+        _ = tokio::time::sleep(duration) => {
+            // The report is built before `future` is dropped, so its trackers are still registered.
+            let report = hang_report().await;
+            tracing::error!(%phase, ?duration, %report, "Timed out.");
+            Err(anyhow::anyhow!("{phase} timed out after {duration:?}\n{report}"))
+        },
+    }
+}
+
 
 pub fn assert_error<T: Debug, E: Debug + Display + Send + Sync + 'static>(result: anyhow::Result<T>) -> anyhow::Result<E> {
     match result {
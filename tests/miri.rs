@@ -0,0 +1,173 @@
+//! Tests that need no server and no networking, so they can run under Miri:
+//! `cargo +nightly miri test --test miri`.
+//! They are a separate target, so Miri does not build the server tests of the library.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use wlist_native_test::{scoped, Tracker};
+
+fn runtime() -> tokio::runtime::Runtime {
+    tokio::runtime::Builder::new_current_thread().enable_time().build().unwrap()
+}
+
+#[tokio::test]
+async fn read_buffer() {
+    let data = vec![1, 2, 3];
+    let buffer = unsafe { wlist_native::core::helper::buffer::new_read_buffer(data.as_ptr(), 3) };
+    let mut iter = buffer.into_iter();
+    assert_eq!(iter.next(), Some(1));
+    assert_eq!(iter.next(), Some(2));
+    assert_eq!(iter.next(), Some(3));
+    assert_eq!(iter.next(), None);
+}
+
+#[tokio::test]
+async fn write_buffer() {
+    use bytes::BufMut;
+    let mut data = vec![0; 3];
+    let mut buffer = unsafe { wlist_native::core::helper::buffer::new_write_buffer(data.as_mut_ptr(), 3) };
+    buffer.put_u8(1);
+    buffer.put_u8(2);
+    buffer.put_u8(3);
+    drop(buffer);
+    assert_eq!(&data, &[1, 2, 3]);
+}
+
+#[tokio::test]
+#[should_panic]
+async fn write_buffer_panic() {
+    use bytes::BufMut;
+    let mut data = vec![0; 3];
+    let mut buffer = unsafe { wlist_native::core::helper::buffer::new_write_buffer(data.as_mut_ptr(), 0) };
+    buffer.put_u8(1);
+}
+
+/// Random `Buf` operations on a read buffer and on `Bytes` must observe the same bytes.
+fn read_buffer_differential(seed: u64, sequences: usize, max_len: usize) {
+    use bytes::Buf;
+    use rand::{Rng, SeedableRng};
+    let mut rng = rand::rngs::StdRng::seed_from_u64(seed);
+    for sequence in 0..sequences {
+        let mut data = vec![0; rng.gen_range(0..=max_len)];
+        rng.fill(&mut data[..]);
+        let mut buffer = unsafe { wlist_native::core::helper::buffer::new_read_buffer(data.as_ptr(), data.len()) };
+        let mut reference = bytes::Bytes::copy_from_slice(&data);
+        for op in 0..64 {
+            let context = format!("sequence {sequence} op {op}");
+            assert_eq!(buffer.remaining(), reference.remaining(), "{context}: remaining");
+            assert_eq!(buffer.has_remaining(), reference.has_remaining(), "{context}: has_remaining");
+            let chunk = buffer.chunk();
+            assert_eq!(chunk.is_empty(), !reference.has_remaining(), "{context}: chunk is empty");
+            assert!(reference.chunk().starts_with(chunk), "{context}: chunk");
+            let n = rng.gen_range(0..=reference.remaining());
+            match rng.gen_range(0..4) {
+                0 => { buffer.advance(n); reference.advance(n); },
+                1 => assert_eq!(buffer.copy_to_bytes(n), reference.copy_to_bytes(n), "{context}: copy_to_bytes({n})"),
+                2 => {
+                    let (mut actual, mut expected) = (vec![0; n], vec![0; n]);
+                    buffer.copy_to_slice(&mut actual);
+                    reference.copy_to_slice(&mut expected);
+                    assert_eq!(actual, expected, "{context}: copy_to_slice({n})");
+                },
+                _ => if reference.has_remaining() {
+                    assert_eq!(buffer.get_u8(), reference.get_u8(), "{context}: get_u8");
+                },
+            }
+        }
+        assert_eq!(buffer.copy_to_bytes(buffer.remaining()), reference, "sequence {sequence}: rest");
+    }
+}
+
+/// Random `BufMut` operations on a write buffer and on a slice must write the same bytes.
+fn write_buffer_differential(seed: u64, sequences: usize, max_len: usize) {
+    use bytes::BufMut;
+    use rand::{Rng, SeedableRng};
+    let mut rng = rand::rngs::StdRng::seed_from_u64(seed);
+    for sequence in 0..sequences {
+        let len = rng.gen_range(0..=max_len);
+        let mut data = vec![0; len];
+        let mut expected = vec![0; len];
+        let mut buffer = unsafe { wlist_native::core::helper::buffer::new_write_buffer(data.as_mut_ptr(), len) };
+        let mut reference = &mut expected[..];
+        for op in 0..64 {
+            let context = format!("sequence {sequence} op {op}");
+            assert_eq!(buffer.remaining_mut(), reference.remaining_mut(), "{context}: remaining_mut");
+            assert_eq!(buffer.has_remaining_mut(), reference.has_remaining_mut(), "{context}: has_remaining_mut");
+            let chunk = buffer.chunk_mut().len();
+            assert!(chunk <= reference.remaining_mut(), "{context}: chunk_mut is {chunk} bytes");
+            assert_eq!(chunk == 0, !reference.has_remaining_mut(), "{context}: chunk_mut is empty");
+            let n = rng.gen_range(0..=reference.remaining_mut());
+            match rng.gen_range(0..3) {
+                0 => {
+                    let mut slice = vec![0; n];
+                    rng.fill(&mut slice[..]);
+                    buffer.put_slice(&slice);
+                    reference.put_slice(&slice);
+                },
+                1 => {
+                    let value = rng.gen();
+                    buffer.put_bytes(value, n);
+                    reference.put_bytes(value, n);
+                },
+                _ => if reference.has_remaining_mut() {
+                    let value = rng.gen();
+                    buffer.put_u8(value);
+                    reference.put_u8(value);
+                },
+            }
+        }
+        drop(buffer);
+        assert_eq!(data, expected, "sequence {sequence}: written bytes");
+    }
+}
+
+/// Miri is slow, so it runs far fewer and shorter sequences.
+#[test]
+fn buffer_differential() {
+    let (sequences, max_len) = if cfg!(miri) { (8, 64) } else { (256, 4096) };
+    read_buffer_differential(0, sequences, max_len);
+    write_buffer_differential(1, sequences, max_len);
+}
+
+/// Stands in for the `InitializeGuard` of the client tests: something every chunk reads through the borrow.
+struct Guard {
+    chunks: Vec<usize>,
+    visited: AtomicUsize,
+}
+
+/// The shape of `upload` and `download0` in the client tests, with the same [scoped] join and [Tracker],
+/// but without the client calls, which need a server.
+async fn transfer(guard: &Guard) -> anyhow::Result<usize> {
+    let tracker = &Tracker::new(&"miri");
+    let chunks = scoped((0..guard.chunks.len()).map(|id| async move {
+        tokio::task::yield_now().await;
+        guard.visited.fetch_add(1, Ordering::Relaxed);
+        tracker.update(format_args!("chunk {id}"));
+        Ok(guard.chunks[id])
+    })).await?;
+    Ok(chunks.into_iter().sum())
+}
+
+#[test]
+fn scoped() -> anyhow::Result<()> {
+    let guard = Guard { chunks: (0..8).collect(), visited: AtomicUsize::new(0) };
+    let sum = runtime().block_on(transfer(&guard))?;
+    assert_eq!(sum, 28);
+    assert_eq!(guard.visited.load(Ordering::Relaxed), 8);
+    Ok(())
+}
+
+/// Drops the transfer halfway, as a test timeout would, and then the guard.
+/// Nothing may touch the guard afterwards.
+#[test]
+fn scoped_cancelled() {
+    let runtime = tokio::runtime::Builder::new_multi_thread().worker_threads(2).enable_time().build().unwrap();
+    let guard = Box::new(Guard { chunks: (0..8).collect(), visited: AtomicUsize::new(0) });
+    runtime.block_on(async {
+        let result = tokio::time::timeout(std::time::Duration::ZERO, transfer(&guard)).await;
+        assert!(result.is_err(), "the transfer is not cancelled");
+    });
+    assert!(guard.visited.load(Ordering::Relaxed) < 8);
+    drop(guard);
+    runtime.block_on(tokio::task::yield_now());
+}